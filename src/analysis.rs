@@ -0,0 +1,255 @@
+use crate::dynamics::wrap_angle_pi;
+use crate::error::Result;
+use crate::integrator::{derive_dt_and_k, AdaptiveStepper, Stepper};
+use crate::settings::{drive_period, DEFAULT_ATOL, DEFAULT_RTOL};
+use crate::simulate::{build_adaptive, build_stepper, Control, Monitor, RunObserver};
+use crate::types::*;
+use rayon::prelude::*;
+use serde::Serialize;
+
+const SEPARATION: f64 = 1e-7;
+// The local FTLE integrates this many orbits over one drive period for every sample.
+const FTLE_ORBITS: usize = 3;
+// Samples between progress reports while colouring by local FTLE.
+const FTLE_CHUNK: usize = 4096;
+
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct Summary {
+	pub min: f64,
+	pub max: f64,
+	pub mean: f64,
+	pub std: f64,
+}
+
+impl Summary {
+	pub fn of(values: impl IntoIterator<Item = f64>) -> Summary {
+		let mut count = 0usize;
+		let mut min = f64::INFINITY;
+		let mut max = f64::NEG_INFINITY;
+		let mut mean = 0.0;
+		let mut m2 = 0.0;
+		for v in values {
+			count += 1;
+			min = min.min(v);
+			max = max.max(v);
+			let delta = v - mean;
+			mean += delta / count as f64;
+			m2 += delta * (v - mean);
+		}
+		if count == 0 {
+			return Summary::default();
+		}
+		Summary { min, max, mean, std: (m2 / count as f64).sqrt() }
+	}
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Analysis {
+	pub samples: usize,
+	pub theta: Summary,
+	pub omega: Summary,
+	pub energy: Summary,
+	pub lyapunov_exponent: Option<f64>,
+	#[serde(skip)]
+	pub ftle: Vec<f64>,
+	pub cancelled: bool,
+}
+
+pub fn energy(theta: f64, omega: f64, phys: &PhysicalParams) -> f64 {
+	0.5 * phys.l * phys.l * omega * omega + phys.g * phys.l * (1.0 - theta.cos())
+}
+
+enum PeriodIntegrator {
+	Fixed { stepper: Box<dyn Stepper>, dt: f64, k: usize },
+	Adaptive { stepper: Box<dyn AdaptiveStepper>, rtol: f64, atol: f64, dt_min: f64, dt_max: f64 },
+}
+
+impl PeriodIntegrator {
+	fn new(spec: &RunSpec) -> Result<Self> {
+		let integ = &spec.integrator;
+		match integ.method {
+			IntegratorMethod::EulerCromer | IntegratorMethod::RK4 => {
+				let (dt, k) = derive_dt_and_k(&spec.phys, integ);
+				Ok(PeriodIntegrator::Fixed { stepper: build_stepper(integ.method)?, dt, k })
+			}
+			IntegratorMethod::RK45 | IntegratorMethod::BulirschStoer => {
+				let period = drive_period(spec.phys.omega_d);
+				Ok(PeriodIntegrator::Adaptive {
+					stepper: build_adaptive(integ.method)?,
+					rtol: integ.rtol.unwrap_or(DEFAULT_RTOL),
+					atol: integ.atol.unwrap_or(DEFAULT_ATOL),
+					dt_min: integ.dt_min.unwrap_or(period / 20000.0),
+					dt_max: integ.dt_max.unwrap_or(period / 20.0),
+				})
+			}
+		}
+	}
+
+	fn advance(&mut self, state: State, phys: &PhysicalParams, period: f64, dt: &mut f64) -> State {
+		match self {
+			PeriodIntegrator::Fixed { stepper, dt: step, k } => {
+				let mut current = state;
+				for _ in 0..*k {
+					current = stepper.step(current, phys, *step);
+				}
+				*dt = *step;
+				current
+			}
+			PeriodIntegrator::Adaptive { stepper, rtol, atol, dt_min, dt_max } => {
+				let target = state.t + period;
+				let (next, used) = stepper.advance_to(state, phys, target, *rtol, *atol, *dt, *dt_min, *dt_max);
+				*dt = used.clamp(*dt_min, *dt_max);
+				next
+			}
+		}
+	}
+}
+
+pub fn analyze(spec: &RunSpec, observer: &mut dyn RunObserver) -> Result<Analysis> {
+	let integ = &spec.integrator;
+	let phys = &spec.phys;
+	let period = drive_period(phys.omega_d);
+	let mut integrator = PeriodIntegrator::new(spec)?;
+	let mut monitor = Monitor::new(observer, integ.n_periods_warmup + integ.n_periods_samples);
+	let mut dt_ref = integ.dt_init.unwrap_or(period / 400.0);
+	let mut reference = State { t: spec.init.t0, theta: spec.init.theta0, omega: spec.init.omega0 };
+	let mut cancelled = false;
+	for _ in 0..integ.n_periods_warmup {
+		reference = integrator.advance(reference, phys, period, &mut dt_ref);
+		if monitor.periods_done(1, dt_ref) == Control::Cancel {
+			cancelled = true;
+			break;
+		}
+	}
+	let mut dt_pert = dt_ref;
+	let mut perturbed = State { theta: reference.theta + SEPARATION, ..reference };
+	let mut samples = Vec::with_capacity(integ.n_periods_samples);
+	let mut ftle = Vec::with_capacity(integ.n_periods_samples);
+	for _ in 0..integ.n_periods_samples {
+		if cancelled {
+			break;
+		}
+		reference = integrator.advance(reference, phys, period, &mut dt_ref);
+		perturbed = integrator.advance(perturbed, phys, period, &mut dt_pert);
+		let d_theta = perturbed.theta - reference.theta;
+		let d_omega = perturbed.omega - reference.omega;
+		let distance = d_theta.hypot(d_omega).max(f64::MIN_POSITIVE);
+		ftle.push((distance / SEPARATION).ln() / period);
+		let scale = SEPARATION / distance;
+		perturbed = State {
+			t: reference.t,
+			theta: reference.theta + d_theta * scale,
+			omega: reference.omega + d_omega * scale,
+		};
+		let theta = if spec.poincare.wrap_to_pi { wrap_angle_pi(reference.theta) } else { reference.theta };
+		samples.push((theta, reference.omega));
+		cancelled = monitor.periods_done(1, dt_ref) == Control::Cancel;
+	}
+	let lyapunov_exponent = if ftle.is_empty() { None } else { Some(ftle.iter().sum::<f64>() / ftle.len() as f64) };
+	Ok(Analysis {
+		samples: samples.len(),
+		theta: Summary::of(samples.iter().map(|s| s.0)),
+		omega: Summary::of(samples.iter().map(|s| s.1)),
+		energy: Summary::of(samples.iter().map(|s| energy(s.0, s.1, phys))),
+		lyapunov_exponent,
+		ftle,
+		cancelled,
+	})
+}
+
+// The quantity `plot.colour_by` maps onto the colour scale, one value per sample; None when the
+// points are drawn in a single colour, or when the observer cancels the integration the local FTLE
+// needs. `spec` supplies the physics the samples were integrated with.
+pub fn point_values(spec: &RunSpec, by: ColourBy, samples: &[SamplePoint], observer: &mut dyn RunObserver) -> Result<Option<Vec<f64>>> {
+	let values = match by {
+		ColourBy::None => return Ok(None),
+		ColourBy::Period => samples.iter().map(|s| s.period as f64).collect(),
+		ColourBy::Energy => samples.iter().map(|s| energy(s.theta, s.omega, &spec.phys)).collect(),
+		ColourBy::Ftle => return local_ftle(spec, samples, observer),
+		ColourBy::Rotation => rotation(samples),
+	};
+	Ok(Some(values))
+}
+
+// Periods of integration that colouring `samples` points by `plot.colour_by` adds to a run.
+pub(crate) fn colouring_periods(plot: &PlotView, samples: usize) -> usize {
+	match (plot.render, plot.colour_by) {
+		(RenderMode::Points, ColourBy::Ftle) => FTLE_ORBITS * samples,
+		_ => 0,
+	}
+}
+
+// Stretching over the drive period after each sample: the largest singular value of the one-period
+// map's Jacobian, estimated from companions displaced by SEPARATION in θ and in ω.
+fn local_ftle(spec: &RunSpec, samples: &[SamplePoint], observer: &mut dyn RunObserver) -> Result<Option<Vec<f64>>> {
+	let phys = &spec.phys;
+	let period = drive_period(phys.omega_d);
+	let dt_init = spec.integrator.dt_init.unwrap_or(period / 400.0);
+	// Surfaces an unsupported method as an error before the per-thread integrators are built.
+	PeriodIntegrator::new(spec)?;
+	let mut monitor = Monitor::new(observer, FTLE_ORBITS * samples.len());
+	let mut values = Vec::with_capacity(samples.len());
+	for chunk in samples.chunks(FTLE_CHUNK) {
+		let stretch = chunk.par_iter().map_init(
+			|| PeriodIntegrator::new(spec).expect("integrator was built above"),
+			|integrator, s| {
+				let start = State { t: s.t, theta: s.theta_unwrapped, omega: s.omega };
+				let mut advance = |state: State| {
+					let mut dt = dt_init;
+					integrator.advance(state, phys, period, &mut dt)
+				};
+				let reference = advance(start);
+				let along_theta = advance(State { theta: start.theta + SEPARATION, ..start });
+				let along_omega = advance(State { omega: start.omega + SEPARATION, ..start });
+				let (a, c) = ((along_theta.theta - reference.theta) / SEPARATION, (along_theta.omega - reference.omega) / SEPARATION);
+				let (b, d) = ((along_omega.theta - reference.theta) / SEPARATION, (along_omega.omega - reference.omega) / SEPARATION);
+				let (p, q, r) = (a * a + c * c, b * b + d * d, a * b + c * d);
+				let stretch = (p + q) / 2.0 + ((p - q) / 2.0).hypot(r);
+				stretch.max(f64::MIN_POSITIVE).ln() / (2.0 * period)
+			},
+		);
+		values.par_extend(stretch);
+		if monitor.periods_done(FTLE_ORBITS * chunk.len(), dt_init) == Control::Cancel {
+			return Ok(None);
+		}
+	}
+	Ok(Some(values))
+}
+
+// Net turns between consecutive samples as -1 (clockwise), 0 (less than half a turn) or 1
+// (anticlockwise); the first sample takes the direction of the step after it.
+fn rotation(samples: &[SamplePoint]) -> Vec<f64> {
+	let turns = |pair: &[SamplePoint]| {
+		let turns = (pair[1].theta_unwrapped - pair[0].theta_unwrapped) / std::f64::consts::TAU;
+		if turns.abs() < 0.5 { 0.0 } else { turns.signum() }
+	};
+	let steps: Vec<f64> = samples.windows(2).map(turns).collect();
+	match steps.first() {
+		Some(&first) => std::iter::once(first).chain(steps).collect(),
+		None => vec![0.0; samples.len()],
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::simulate::{NoObserver, Progress};
+	use crate::test_support::{outcome, spec};
+
+	#[test]
+	fn local_ftle_reports_progress_and_stops_on_cancel() {
+		let (spec, samples) = (spec(), outcome(10).samples);
+		let mut reported = 0;
+		let mut count = |progress: &Progress| {
+			reported = progress.periods_done;
+			Control::Continue
+		};
+		let values = point_values(&spec, ColourBy::Ftle, &samples, &mut count).unwrap().unwrap();
+		assert_eq!(values.len(), samples.len());
+		assert_eq!(reported, FTLE_ORBITS * samples.len());
+		let mut cancel = |_: &Progress| Control::Cancel;
+		assert!(point_values(&spec, ColourBy::Ftle, &samples, &mut cancel).unwrap().is_none());
+		assert!(point_values(&spec, ColourBy::Energy, &samples, &mut cancel).unwrap().is_some());
+		assert_eq!(point_values(&spec, ColourBy::Period, &samples, &mut NoObserver).unwrap(), Some((1..=10).map(f64::from).collect()));
+	}
+}
@@ -0,0 +1,338 @@
+use crate::analysis::colouring_periods;
+use crate::diagnostics::Diagnostic;
+use crate::error::{Error, Result};
+use crate::format::decimal_places;
+use crate::schema::{base_schema, is_numeric, object_schema, resolve_ref, schema_allows};
+use crate::settings::{apply_override, load_spec_value, resolve_run_spec, set_path, LoadedSpec};
+use crate::simulate::{run, Control, Progress, RunObserver, RunOutcome};
+use crate::types::RunSpec;
+use rayon::prelude::*;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+const MAX_BATCH_RUNS: usize = 100_000;
+
+#[derive(Clone, Debug)]
+pub struct SweepAxis {
+	pub path: String,
+	pub values: Vec<f64>,
+	pub integer: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SweepValue {
+	pub path: String,
+	pub value: f64,
+}
+
+pub fn load_run_specs<S: AsRef<str>>(path: &str, overrides: &[S]) -> Result<Vec<LoadedSpec>> {
+	let mut value = load_spec_value(path)?;
+	for assignment in overrides {
+		apply_override(&mut value, assignment.as_ref())?;
+	}
+	expand_sweeps(&value)?
+		.into_iter()
+		.map(|(expanded, sweep)| {
+			let mut loaded = resolve_run_spec(expanded, path)?;
+			loaded.sweep = sweep;
+			Ok(loaded)
+		})
+		.collect()
+}
+
+pub fn sweep_suffix(sweep: &[SweepValue]) -> String {
+	sweep
+		.iter()
+		.map(|s| {
+			let key = s.path.rsplit('.').next().unwrap_or(&s.path);
+			format!("__{key}={}", s.value)
+		})
+		.collect()
+}
+
+pub fn find_sweeps(value: &Value) -> Result<Vec<SweepAxis>> {
+	let schema = base_schema();
+	let mut axes = Vec::new();
+	let mut problems = Vec::new();
+	collect_sweeps(value, &schema, &schema, "", &mut axes, &mut problems);
+	if problems.is_empty() {
+		Ok(axes)
+	} else {
+		Err(Error::InvalidSpec(problems))
+	}
+}
+
+pub fn expand_sweeps(value: &Value) -> Result<Vec<(Value, Vec<SweepValue>)>> {
+	let axes = find_sweeps(value)?;
+	if axes.is_empty() {
+		return Ok(vec![(value.clone(), Vec::new())]);
+	}
+	let total = axes.iter().try_fold(1usize, |acc, axis| acc.checked_mul(axis.values.len()));
+	match total {
+		Some(n) if n <= MAX_BATCH_RUNS => {}
+		_ => {
+			return Err(Error::InvalidSpec(vec![Diagnostic::error(
+				&axes[0].path,
+				format!("sweep expands to more than {MAX_BATCH_RUNS} runs"),
+			)]))
+		}
+	}
+	let base_name = value
+		.pointer("/output/out_base")
+		.and_then(Value::as_str)
+		.unwrap_or("")
+		.to_string();
+	let mut expanded = Vec::new();
+	let mut index = vec![0usize; axes.len()];
+	loop {
+		let mut run_value = value.clone();
+		let mut sweep = Vec::with_capacity(axes.len());
+		for (axis, &i) in axes.iter().zip(&index) {
+			let v = axis.values[i];
+			let number = if axis.integer { Value::from(v as i64) } else { Value::from(v) };
+			set_path(&mut run_value, &axis.path, number);
+			sweep.push(SweepValue { path: axis.path.clone(), value: v });
+		}
+		let name = format!("{base_name}{}", sweep_suffix(&sweep));
+		set_path(&mut run_value, "output.out_base", Value::String(name));
+		expanded.push((run_value, sweep));
+		let mut digit = axes.len();
+		loop {
+			if digit == 0 {
+				return Ok(expanded);
+			}
+			digit -= 1;
+			index[digit] += 1;
+			if index[digit] < axes[digit].values.len() {
+				break;
+			}
+			index[digit] = 0;
+		}
+	}
+}
+
+fn collect_sweeps(
+	value: &Value,
+	node: &Value,
+	root: &Value,
+	path: &str,
+	axes: &mut Vec<SweepAxis>,
+	problems: &mut Vec<Diagnostic>,
+) {
+	let node = object_schema(node, root);
+	let (Some(map), Some(properties)) = (value.as_object(), node.get("properties").and_then(Value::as_object)) else {
+		return;
+	};
+	for (key, child) in map {
+		let Some(child_schema) = properties.get(key) else {
+			continue;
+		};
+		let child_schema = resolve_ref(child_schema, root);
+		let child_path = if path.is_empty() { key.clone() } else { format!("{path}.{key}") };
+		if is_numeric(child_schema.get("type")) {
+			let integer = !schema_allows(child_schema, "number");
+			match sweep_values(child, &child_path, integer) {
+				Ok(Some(values)) => axes.push(SweepAxis { path: child_path, values, integer }),
+				Ok(None) => {}
+				Err(problem) => problems.push(problem),
+			}
+		} else {
+			collect_sweeps(child, child_schema, root, &child_path, axes, problems);
+		}
+	}
+}
+
+fn sweep_values(value: &Value, path: &str, integer: bool) -> std::result::Result<Option<Vec<f64>>, Diagnostic> {
+	let values = match value {
+		Value::Array(items) => {
+			if items.is_empty() {
+				return Err(Diagnostic::error(path, "sweep list must not be empty"));
+			}
+			items
+				.iter()
+				.map(|item| item.as_f64().ok_or_else(|| Diagnostic::error(path, "sweep list must contain only numbers")))
+				.collect::<std::result::Result<Vec<f64>, Diagnostic>>()?
+		}
+		Value::Object(map) => {
+			let range = map
+				.get("range")
+				.and_then(Value::as_array)
+				.filter(|r| r.len() == 3)
+				.and_then(|r| r.iter().map(Value::as_f64).collect::<Option<Vec<f64>>>())
+				.ok_or_else(|| Diagnostic::error(path, "expected {\"range\": [start, end, step]}"))?;
+			expand_range(range[0], range[1], range[2]).map_err(|message| Diagnostic::error(path, message))?
+		}
+		_ => return Ok(None),
+	};
+	if integer && values.iter().any(|v| v.fract() != 0.0) {
+		return Err(Diagnostic::error(path, "sweep values must be whole numbers"));
+	}
+	Ok(Some(values))
+}
+
+fn expand_range(start: f64, end: f64, step: f64) -> std::result::Result<Vec<f64>, String> {
+	if !(start.is_finite() && end.is_finite() && step.is_finite()) || step == 0.0 {
+		return Err("range bounds must be finite and step non-zero".to_string());
+	}
+	if (end - start) * step < 0.0 {
+		return Err("range step points away from the end value".to_string());
+	}
+	let count = ((end - start) / step + 1e-9).floor() as usize + 1;
+	if count > MAX_BATCH_RUNS {
+		return Err(format!("range has more than {MAX_BATCH_RUNS} values"));
+	}
+	let decimals = decimal_places(start).max(decimal_places(step));
+	Ok((0..count)
+		.map(|i| {
+			let v = start + i as f64 * step;
+			format!("{v:.decimals$}").parse().unwrap_or(v)
+		})
+		.collect())
+}
+
+// Runs every spec in parallel and hands each outcome to `finish`, along with an observer for any
+// further integration it does; `observer` sees the progress of all of them as one total.
+pub fn run_batch<O, F>(specs: &[RunSpec], observer: &mut O, finish: F) -> Vec<Result<()>>
+where
+	O: RunObserver + Send,
+	F: Fn(&RunSpec, RunOutcome, &mut dyn RunObserver) -> Result<()> + Sync,
+{
+	let shared = SharedProgress {
+		observer: Mutex::new(observer),
+		periods_done: AtomicUsize::new(0),
+		periods_total: specs
+			.iter()
+			.map(|s| {
+				let samples = s.integrator.n_periods_samples;
+				s.integrator.n_periods_warmup + samples + colouring_periods(&s.plot, samples)
+			})
+			.sum(),
+		cancelled: AtomicBool::new(false),
+		start: Instant::now(),
+	};
+	let shared = &shared;
+	// An observer for one stage of one run, which counts its periods from zero.
+	let forward = || {
+		let mut last_done = 0;
+		move |progress: &Progress| {
+			let delta = progress.periods_done - last_done;
+			last_done = progress.periods_done;
+			shared.report(delta, progress.dt)
+		}
+	};
+	specs
+		.par_iter()
+		.map(|spec| {
+			let outcome = run(spec, &mut forward())?;
+			finish(spec, outcome, &mut forward())
+		})
+		.collect()
+}
+
+struct SharedProgress<'a, O: RunObserver> {
+	observer: Mutex<&'a mut O>,
+	periods_done: AtomicUsize,
+	periods_total: usize,
+	cancelled: AtomicBool,
+	start: Instant,
+}
+
+impl<O: RunObserver> SharedProgress<'_, O> {
+	fn report(&self, delta: usize, dt: f64) -> Control {
+		let done = self.periods_done.fetch_add(delta, Ordering::Relaxed) + delta;
+		// Skip the report while another run holds the observer, except for the final one.
+		let guard = if done >= self.periods_total { self.observer.lock().ok() } else { self.observer.try_lock().ok() };
+		if let Some(mut observer) = guard {
+			let elapsed = self.start.elapsed();
+			let eta = (done > 0).then(|| elapsed.mul_f64((self.periods_total - done) as f64 / done as f64));
+			let progress = Progress { periods_done: done, periods_total: self.periods_total, elapsed, eta, dt };
+			if observer.on_progress(&progress) == Control::Cancel {
+				self.cancelled.store(true, Ordering::Relaxed);
+			}
+		}
+		if self.cancelled.load(Ordering::Relaxed) {
+			Control::Cancel
+		} else {
+			Control::Continue
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_support::spec_value;
+	use serde_json::json;
+
+	#[test]
+	fn range_includes_both_endpoints() {
+		assert_eq!(expand_range(1.0, 1.5, 0.1).unwrap(), vec![1.0, 1.1, 1.2, 1.3, 1.4, 1.5]);
+		assert_eq!(expand_range(0.0, 1.0, 0.25).unwrap(), vec![0.0, 0.25, 0.5, 0.75, 1.0]);
+		assert_eq!(expand_range(1.0, 0.0, -0.5).unwrap(), vec![1.0, 0.5, 0.0]);
+		assert_eq!(expand_range(2.0, 2.0, 1.0).unwrap(), vec![2.0]);
+		// An end that the step does not reach exactly is excluded.
+		assert_eq!(expand_range(0.0, 1.0, 0.3).unwrap(), vec![0.0, 0.3, 0.6, 0.9]);
+	}
+
+	#[test]
+	fn range_rejects_bad_steps() {
+		assert!(expand_range(0.0, 1.0, 0.0).is_err());
+		assert!(expand_range(0.0, 1.0, -0.1).is_err());
+		assert!(expand_range(0.0, f64::INFINITY, 1.0).is_err());
+		assert!(expand_range(0.0, 1e9, 1.0).is_err());
+	}
+
+	#[test]
+	fn sweeps_expand_to_the_cartesian_product() {
+		let mut value = spec_value();
+		value["phys"]["f_drive"] = json!([1.35, 1.5]);
+		value["integrator"]["n_periods_samples"] = json!({ "range": [100, 300, 100] });
+		let runs = expand_sweeps(&value).unwrap();
+		let names: Vec<&str> = runs.iter().map(|(v, _)| v["output"]["out_base"].as_str().unwrap()).collect();
+		// Axes follow the spec's key order, and the last axis varies fastest.
+		assert_eq!(
+			names,
+			[
+				"t__n_periods_samples=100__f_drive=1.35",
+				"t__n_periods_samples=100__f_drive=1.5",
+				"t__n_periods_samples=200__f_drive=1.35",
+				"t__n_periods_samples=200__f_drive=1.5",
+				"t__n_periods_samples=300__f_drive=1.35",
+				"t__n_periods_samples=300__f_drive=1.5",
+			]
+		);
+		let (last, sweep) = runs.last().unwrap();
+		assert_eq!(last["phys"]["f_drive"], json!(1.5));
+		assert_eq!(last["integrator"]["n_periods_samples"], json!(300));
+		assert!(last["integrator"]["n_periods_samples"].is_u64());
+		assert_eq!(sweep.iter().map(|s| s.path.as_str()).collect::<Vec<_>>(), ["integrator.n_periods_samples", "phys.f_drive"]);
+	}
+
+	#[test]
+	fn sweeps_are_validated() {
+		assert_eq!(expand_sweeps(&spec_value()).unwrap().len(), 1);
+		for bad in [json!([]), json!(["a"]), json!({ "range": [0, 1] }), json!([1.5, 2])] {
+			let mut value = spec_value();
+			value["integrator"]["n_periods_warmup"] = bad.clone();
+			assert!(matches!(expand_sweeps(&value), Err(Error::InvalidSpec(_))), "{bad} was accepted");
+		}
+		// Lists under non-numeric fields are left alone.
+		let mut value = spec_value();
+		value["plot"]["x_range"] = json!([0, 3]);
+		assert_eq!(expand_sweeps(&value).unwrap().len(), 1);
+	}
+
+	#[test]
+	fn sweeps_reach_optional_sections() {
+		let mut value = spec_value();
+		value["plot"]["tiles"] = json!({ "levels": [1, 2] });
+		let runs = expand_sweeps(&value).unwrap();
+		assert_eq!(runs.iter().map(|(v, _)| v["plot"]["tiles"]["levels"].clone()).collect::<Vec<_>>(), [json!(1), json!(2)]);
+		assert_eq!(runs[0].1[0].path, "plot.tiles.levels");
+		value["plot"]["tiles"] = json!({ "levels": [1.5] });
+		assert!(matches!(expand_sweeps(&value), Err(Error::InvalidSpec(_))));
+	}
+}
@@ -0,0 +1,113 @@
+pub const USAGE: &str = "\
+usage: pendulum-poincare <command> [SPEC] [options]
+
+commands:
+  run       integrate the spec and write the figures and output/<out_base>.csv (default)
+  plot      integrate the spec and write only the figures, or re-plot saved samples with --data
+  sweep     run every combination of the spec's list/range values in parallel
+  analyze   integrate the spec and print sample statistics and the largest Lyapunov exponent
+  validate  report every problem in the spec without running it
+  schema    print the JSON Schema of the run spec
+
+options:
+  --set key.path=value    override a spec field after loading (repeatable)
+  --vary key.path=a:b:s   sweep a field over [a, b] with step s, or over a,b,c (sweep only)
+  --data FILE             plot samples from a .csv or .ppb written by run instead of integrating;
+                          the plot settings come from SPEC when given, else from the first file;
+                          repeat to overlay several runs in one figure (plot only)
+  --print-effective       print the resolved spec as JSON instead of running (run, plot, validate)
+  -h, --help              show this message
+
+SPEC defaults to run.json; .toml and .yaml/.yml files are also accepted.";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+	Run,
+	Plot,
+	Sweep,
+	Analyze,
+	Validate,
+	Schema,
+	Help,
+}
+
+#[derive(Debug)]
+pub struct Cli {
+	pub command: Command,
+	pub spec: Option<String>,
+	pub data: Vec<String>,
+	pub overrides: Vec<String>,
+	pub print_effective: bool,
+}
+
+impl Cli {
+	pub fn spec(&self) -> &str {
+		self.spec.as_deref().unwrap_or("run.json")
+	}
+}
+
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Cli, String> {
+	let mut args = args.into_iter().peekable();
+	let command = match args.peek().map(String::as_str) {
+		Some("run") => Command::Run,
+		Some("plot") => Command::Plot,
+		Some("sweep") => Command::Sweep,
+		Some("analyze") => Command::Analyze,
+		Some("validate") => Command::Validate,
+		Some("schema") => Command::Schema,
+		Some("help") => Command::Help,
+		_ => {
+			return parse_options(Command::Run, args);
+		}
+	};
+	args.next();
+	parse_options(command, args)
+}
+
+fn parse_options(command: Command, args: impl Iterator<Item = String>) -> Result<Cli, String> {
+	let mut cli = Cli { command, spec: None, data: Vec::new(), overrides: Vec::new(), print_effective: false };
+	let mut args = args;
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"-h" | "--help" => cli.command = Command::Help,
+			"--print-effective" => cli.print_effective = true,
+			"--set" => cli.overrides.push(args.next().ok_or("--set needs a key.path=value argument")?),
+			"--vary" if command == Command::Sweep => {
+				let vary = args.next().ok_or("--vary needs a key.path=values argument")?;
+				cli.overrides.push(vary_override(&vary)?);
+			}
+			"--vary" => return Err("--vary is only valid with the sweep command".to_string()),
+			"--data" if command == Command::Plot => cli.data.push(args.next().ok_or("--data needs a file argument")?),
+			"--data" => return Err("--data is only valid with the plot command".to_string()),
+			other if other.starts_with("--set=") => cli.overrides.push(other["--set=".len()..].to_string()),
+			other if other.starts_with('-') => return Err(format!("unknown option {other}")),
+			_ if cli.spec.is_none() => cli.spec = Some(arg),
+			_ => return Err(format!("unexpected argument {arg}")),
+		}
+	}
+	Ok(cli)
+}
+
+fn vary_override(vary: &str) -> Result<String, String> {
+	let (path, values) = vary.split_once('=').ok_or("--vary must have the form key.path=values")?;
+	let numbers = |parts: Vec<&str>| {
+		parts
+			.iter()
+			.map(|p| p.trim().parse::<f64>().map_err(|_| format!("--vary {path}: {p:?} is not a number")))
+			.collect::<Result<Vec<f64>, String>>()
+	};
+	let parts: Vec<&str> = values.split(':').collect();
+	let json = match parts.len() {
+		3 => {
+			let range = numbers(parts)?;
+			format!("{{\"range\": [{}, {}, {}]}}", range[0], range[1], range[2])
+		}
+		1 => {
+			let list = numbers(values.split(',').collect())?;
+			let items: Vec<String> = list.iter().map(f64::to_string).collect();
+			format!("[{}]", items.join(", "))
+		}
+		_ => return Err(format!("--vary {path}: expected start:end:step or a,b,c")),
+	};
+	Ok(format!("{path}={json}"))
+}
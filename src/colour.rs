@@ -0,0 +1,34 @@
+// 0xrrggbb as RGB bytes.
+pub(crate) fn hex_rgb(hex: u32) -> [u8; 3] {
+	[(hex >> 16) as u8, (hex >> 8) as u8, hex as u8]
+}
+
+// "#rrggbb" or "#rgb".
+pub(crate) fn parse_colour(text: &str) -> Option<[u8; 3]> {
+	let digits = text.strip_prefix('#')?;
+	if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+		return None;
+	}
+	let hex = u32::from_str_radix(digits, 16).ok()?;
+	match digits.len() {
+		6 => Some(hex_rgb(hex)),
+		3 => Some([hex >> 8, (hex >> 4) & 0xf, hex & 0xf].map(|c| (c * 17) as u8)),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_hex_colours() {
+		assert_eq!(parse_colour("#1f77b4"), Some([0x1f, 0x77, 0xb4]));
+		assert_eq!(parse_colour("#FFF"), Some([255, 255, 255]));
+		assert_eq!(parse_colour("#08c"), Some([0x00, 0x88, 0xcc]));
+		for bad in ["1f77b4", "#1f77b", "#+1f7b4", "#ggg", "#", "red"] {
+			assert_eq!(parse_colour(bad), None, "{bad}");
+		}
+		assert_eq!(hex_rgb(0x123456), [0x12, 0x34, 0x56]);
+	}
+}
@@ -0,0 +1,309 @@
+use crate::dynamics::wrap_angle_pi;
+use crate::error::{Error, Result};
+use crate::npy::crc32;
+use crate::simulate::RunOutcome;
+use crate::types::{IntegratorStats, RunSpec, SamplePoint};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+
+// PNG-style signature: the high byte and line endings expose text-mode or 7-bit mangling.
+pub const MAGIC: [u8; 8] = *b"\x89PPB\r\n\x1a\n";
+pub const VERSION: u16 = 1;
+pub const COLUMNS: [&str; 4] = ["period", "t", "theta_unwrapped", "omega"];
+const FLAG_COMPRESSED: u16 = 1;
+const BLOCK_SAMPLES: usize = 65536;
+const MAX_HEADER_BYTES: u32 = 64 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+	generator: String,
+	columns: Vec<String>,
+	samples: u64,
+	cancelled: bool,
+	stats: IntegratorStats,
+	spec: RunSpec,
+}
+
+#[derive(Clone, Debug)]
+pub struct Container {
+	pub version: u16,
+	pub generator: String,
+	pub spec: RunSpec,
+	pub stats: IntegratorStats,
+	pub cancelled: bool,
+	pub samples: Vec<SamplePoint>,
+}
+
+impl Container {
+	pub fn into_outcome(self) -> (RunSpec, RunOutcome) {
+		(self.spec, RunOutcome { samples: self.samples, cancelled: self.cancelled, stats: self.stats })
+	}
+}
+
+pub fn write_container(path: &Path, spec: &RunSpec, outcome: &RunOutcome, compress: bool) -> Result<()> {
+	let file = File::create(path).map_err(|e| Error::io(path, e))?;
+	write_container_to(BufWriter::new(file), spec, outcome, compress).map_err(|e| Error::io(path, e))
+}
+
+fn write_container_to(mut out: impl Write, spec: &RunSpec, outcome: &RunOutcome, compress: bool) -> std::io::Result<()> {
+	let header = Header {
+		generator: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+		columns: COLUMNS.iter().map(|c| c.to_string()).collect(),
+		samples: outcome.samples.len() as u64,
+		cancelled: outcome.cancelled,
+		stats: outcome.stats,
+		spec: spec.clone(),
+	};
+	let header = serde_json::to_vec(&header).expect("container header serialises to JSON");
+	out.write_all(&MAGIC)?;
+	out.write_all(&VERSION.to_le_bytes())?;
+	out.write_all(&(if compress { FLAG_COMPRESSED } else { 0 }).to_le_bytes())?;
+	out.write_all(&(header.len() as u32).to_le_bytes())?;
+	out.write_all(&header)?;
+	out.write_all(&crc32(&header).to_le_bytes())?;
+	for block in outcome.samples.chunks(BLOCK_SAMPLES) {
+		let mut raw = Vec::with_capacity(block.len() * COLUMNS.len() * 8);
+		for s in block {
+			for v in [s.period as f64, s.t, s.theta_unwrapped, s.omega] {
+				raw.extend_from_slice(&v.to_le_bytes());
+			}
+		}
+		let crc = crc32(&raw);
+		let stored = if compress {
+			let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+			encoder.write_all(&raw)?;
+			encoder.finish()?
+		} else {
+			raw
+		};
+		write_block_header(&mut out, block.len() as u32, stored.len() as u32, crc)?;
+		out.write_all(&stored)?;
+	}
+	write_block_header(&mut out, 0, 0, 0)?;
+	out.flush()
+}
+
+fn write_block_header(out: &mut impl Write, samples: u32, stored_len: u32, crc: u32) -> std::io::Result<()> {
+	out.write_all(&samples.to_le_bytes())?;
+	out.write_all(&stored_len.to_le_bytes())?;
+	out.write_all(&crc.to_le_bytes())
+}
+
+pub fn read_container(path: &Path) -> Result<Container> {
+	let file = File::open(path).map_err(|e| Error::io(path, e))?;
+	read_container_from(BufReader::new(file)).map_err(|e| match e {
+		ReadError::Io(e) if e.kind() == ErrorKind::UnexpectedEof => Error::parse(path, "file is truncated"),
+		ReadError::Io(e) => Error::io(path, e),
+		ReadError::Format(message) => Error::parse(path, message),
+	})
+}
+
+enum ReadError {
+	Io(std::io::Error),
+	Format(String),
+}
+
+impl From<std::io::Error> for ReadError {
+	fn from(e: std::io::Error) -> Self {
+		ReadError::Io(e)
+	}
+}
+
+fn read_container_from(mut input: impl Read) -> std::result::Result<Container, ReadError> {
+	let mut magic = [0u8; 8];
+	input.read_exact(&mut magic)?;
+	if magic != MAGIC {
+		return Err(ReadError::Format("not a Poincaré sample container".to_string()));
+	}
+	let version = read_u16(&mut input)?;
+	if version == 0 || version > VERSION {
+		return Err(ReadError::Format(format!("unsupported container version {version} (this build reads up to {VERSION})")));
+	}
+	let flags = read_u16(&mut input)?;
+	let header_len = read_u32(&mut input)?;
+	if header_len > MAX_HEADER_BYTES {
+		return Err(ReadError::Format(format!("header length {header_len} is implausibly large")));
+	}
+	let mut header = vec![0u8; header_len as usize];
+	input.read_exact(&mut header)?;
+	if read_u32(&mut input)? != crc32(&header) {
+		return Err(ReadError::Format("header checksum mismatch".to_string()));
+	}
+	let header: Header =
+		serde_json::from_slice(&header).map_err(|e| ReadError::Format(format!("invalid header: {e}")))?;
+	if header.columns != COLUMNS {
+		return Err(ReadError::Format(format!("unexpected columns {:?}", header.columns)));
+	}
+	let wrap = header.spec.poincare.wrap_to_pi;
+	let mut samples = Vec::with_capacity(header.samples.min(1 << 24) as usize);
+	loop {
+		let count = read_u32(&mut input)? as usize;
+		let stored_len = read_u32(&mut input)? as usize;
+		let crc = read_u32(&mut input)?;
+		if count == 0 {
+			break;
+		}
+		if count > BLOCK_SAMPLES {
+			return Err(ReadError::Format(format!("block of {count} samples exceeds the limit of {BLOCK_SAMPLES}")));
+		}
+		let raw_len = count * COLUMNS.len() * 8;
+		// Deflate never grows data by more than a few bytes per 16 KiB block.
+		if stored_len > raw_len + raw_len / 1024 + 64 {
+			return Err(ReadError::Format(format!("block length {stored_len} is implausibly large")));
+		}
+		let mut stored = vec![0u8; stored_len];
+		input.read_exact(&mut stored)?;
+		let raw = if flags & FLAG_COMPRESSED != 0 {
+			let mut raw = Vec::with_capacity(raw_len);
+			ZlibDecoder::new(stored.as_slice())
+				.take(raw_len as u64 + 1)
+				.read_to_end(&mut raw)
+				.map_err(|e| ReadError::Format(format!("corrupt compressed block: {e}")))?;
+			raw
+		} else {
+			stored
+		};
+		if raw.len() != raw_len || crc32(&raw) != crc {
+			return Err(ReadError::Format(format!("sample block {} failed its checksum", samples.len() / BLOCK_SAMPLES)));
+		}
+		for row in raw.chunks_exact(COLUMNS.len() * 8) {
+			let value = |i: usize| f64::from_le_bytes(row[i * 8..i * 8 + 8].try_into().expect("8-byte column"));
+			let theta_unwrapped = value(2);
+			samples.push(SamplePoint {
+				period: value(0) as usize,
+				t: value(1),
+				theta: if wrap { wrap_angle_pi(theta_unwrapped) } else { theta_unwrapped },
+				theta_unwrapped,
+				omega: value(3),
+			});
+		}
+	}
+	if samples.len() as u64 != header.samples {
+		return Err(ReadError::Format(format!("header lists {} samples but the file holds {}", header.samples, samples.len())));
+	}
+	Ok(Container {
+		version,
+		generator: header.generator,
+		spec: header.spec,
+		stats: header.stats,
+		cancelled: header.cancelled,
+		samples,
+	})
+}
+
+fn read_u16(input: &mut impl Read) -> std::io::Result<u16> {
+	let mut bytes = [0u8; 2];
+	input.read_exact(&mut bytes)?;
+	Ok(u16::from_le_bytes(bytes))
+}
+
+fn read_u32(input: &mut impl Read) -> std::io::Result<u32> {
+	let mut bytes = [0u8; 4];
+	input.read_exact(&mut bytes)?;
+	Ok(u32::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_support::{outcome, spec};
+
+	fn written(samples: usize, compress: bool) -> Vec<u8> {
+		let mut bytes = Vec::new();
+		write_container_to(&mut bytes, &spec(), &outcome(samples), compress).unwrap();
+		bytes
+	}
+
+	fn format_error(bytes: &[u8]) -> String {
+		match read_container_from(bytes) {
+			Err(ReadError::Format(message)) => message,
+			Err(ReadError::Io(e)) => panic!("expected a format error, got {e}"),
+			Ok(_) => panic!("corrupt container was accepted"),
+		}
+	}
+
+	#[test]
+	fn round_trip() {
+		// More than one block, with a partial last block.
+		let expected = outcome(BLOCK_SAMPLES + 10);
+		for compress in [false, true] {
+			let container = read_container_from(written(BLOCK_SAMPLES + 10, compress).as_slice()).ok().unwrap();
+			assert_eq!(container.version, VERSION);
+			assert_eq!(container.stats, expected.stats);
+			assert!(!container.cancelled);
+			assert_eq!(container.spec.output.out_base, "t");
+			assert_eq!(container.samples.len(), expected.samples.len());
+			for (read, original) in container.samples.iter().zip(&expected.samples) {
+				assert_eq!(read.period, original.period);
+				assert_eq!(read.t.to_bits(), original.t.to_bits());
+				assert_eq!(read.theta.to_bits(), original.theta.to_bits());
+				assert_eq!(read.theta_unwrapped.to_bits(), original.theta_unwrapped.to_bits());
+				assert_eq!(read.omega.to_bits(), original.omega.to_bits());
+			}
+		}
+	}
+
+	#[test]
+	fn byte_layout() {
+		let bytes = written(3, false);
+		assert_eq!(&bytes[..8], &MAGIC);
+		assert_eq!(u16::from_le_bytes([bytes[8], bytes[9]]), VERSION);
+		assert_eq!(u16::from_le_bytes([bytes[10], bytes[11]]), 0);
+		let header_len = u32::from_le_bytes(bytes[12..16].try_into().unwrap()) as usize;
+		let header = &bytes[16..16 + header_len];
+		let crc = u32::from_le_bytes(bytes[16 + header_len..20 + header_len].try_into().unwrap());
+		assert_eq!(crc, crc32(header));
+		let header: serde_json::Value = serde_json::from_slice(header).unwrap();
+		assert_eq!(header["samples"], 3);
+		assert_eq!(header["columns"], serde_json::json!(COLUMNS));
+
+		let block = 20 + header_len;
+		let word = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+		assert_eq!((word(block), word(block + 4)), (3, 3 * 32));
+		let first = block + 12;
+		assert_eq!(word(block + 8), crc32(&bytes[first..first + 96]));
+		assert_eq!(f64::from_le_bytes(bytes[first..first + 8].try_into().unwrap()), 1.0);
+		let end = first + 96;
+		assert_eq!(&bytes[end..], &[0; 12]);
+
+		assert_eq!(u16::from_le_bytes([written(3, true)[10], 0]), FLAG_COMPRESSED);
+	}
+
+	#[test]
+	fn corruption_is_reported() {
+		let clean = written(100, false);
+		let header_len = u32::from_le_bytes(clean[12..16].try_into().unwrap()) as usize;
+
+		let mut bytes = clean.clone();
+		bytes[16 + header_len / 2] ^= 0x01;
+		assert_eq!(format_error(&bytes), "header checksum mismatch");
+
+		let mut bytes = clean.clone();
+		let last_sample_byte = bytes.len() - 13;
+		bytes[last_sample_byte] ^= 0x40;
+		assert!(format_error(&bytes).contains("failed its checksum"));
+
+		let mut bytes = written(100, true);
+		let compressed = bytes.len() - 20;
+		bytes[compressed] ^= 0xff;
+		format_error(&bytes);
+
+		let mut bytes = clean.clone();
+		bytes[8] = 9;
+		assert!(format_error(&bytes).contains("unsupported container version 9"));
+
+		let mut bytes = clean.clone();
+		bytes[0] = b'%';
+		assert_eq!(format_error(&bytes), "not a Poincaré sample container");
+
+		match read_container_from(&clean[..clean.len() - 40]) {
+			Err(ReadError::Io(e)) => assert_eq!(e.kind(), ErrorKind::UnexpectedEof),
+			_ => panic!("truncated container was accepted"),
+		}
+	}
+}
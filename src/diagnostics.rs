@@ -0,0 +1,44 @@
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+	Warning,
+	Error,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+	pub severity: Severity,
+	pub path: String,
+	pub message: String,
+}
+
+impl Diagnostic {
+	pub fn error(path: &str, message: impl Into<String>) -> Self {
+		Self { severity: Severity::Error, path: path.to_string(), message: message.into() }
+	}
+
+	pub fn warning(path: &str, message: impl Into<String>) -> Self {
+		Self { severity: Severity::Warning, path: path.to_string(), message: message.into() }
+	}
+
+	pub fn is_error(&self) -> bool {
+		self.severity == Severity::Error
+	}
+}
+
+impl fmt::Display for Diagnostic {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let label = match self.severity {
+			Severity::Warning => "warning",
+			Severity::Error => "error",
+		};
+		write!(f, "{label}: {}: {}", self.path, self.message)
+	}
+}
+
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+	diagnostics.iter().any(Diagnostic::is_error)
+}
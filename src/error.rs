@@ -0,0 +1,58 @@
+use crate::diagnostics::Diagnostic;
+use crate::types::IntegratorMethod;
+use std::fmt;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum Error {
+	Io { path: PathBuf, source: std::io::Error },
+	Parse { path: PathBuf, message: String },
+	InvalidSpec(Vec<Diagnostic>),
+	UnsupportedMethod { method: IntegratorMethod, expected: &'static str },
+	Plot { path: PathBuf, message: String },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+	pub fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+		Error::Io { path: path.into(), source }
+	}
+
+	pub fn parse(path: impl Into<PathBuf>, message: impl fmt::Display) -> Self {
+		Error::Parse { path: path.into(), message: message.to_string() }
+	}
+
+	pub fn plot(path: impl Into<PathBuf>, message: impl fmt::Display) -> Self {
+		Error::Plot { path: path.into(), message: message.to_string() }
+	}
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Error::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+			Error::Parse { path, message } => write!(f, "{}: {}", path.display(), message),
+			Error::InvalidSpec(diagnostics) => {
+				write!(f, "invalid run spec")?;
+				for diagnostic in diagnostics {
+					write!(f, "\n  {diagnostic}")?;
+				}
+				Ok(())
+			}
+			Error::UnsupportedMethod { method, expected } => {
+				write!(f, "{method:?} is not a {expected} integrator")
+			}
+			Error::Plot { path, message } => write!(f, "failed to render {}: {}", path.display(), message),
+		}
+	}
+}
+
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Error::Io { source, .. } => Some(source),
+			_ => None,
+		}
+	}
+}
@@ -0,0 +1,136 @@
+use crate::batch::SweepValue;
+use crate::container::write_container;
+use crate::dynamics::wrap_angle_pi;
+use crate::error::{Error, Result};
+use crate::npy::{write_npy, write_npz, NpyArray};
+use crate::output::output_file;
+use crate::simulate::RunOutcome;
+use crate::types::{DataFormat, RunSpec, SamplePoint};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+pub const CSV_COLUMNS: [&str; 5] = ["period", "t", "theta_wrapped", "theta_unwrapped", "omega"];
+
+pub fn write_data(spec: &RunSpec, outcome: &RunOutcome) -> Result<Vec<PathBuf>> {
+	spec.output
+		.data
+		.iter()
+		.map(|format| match format {
+			DataFormat::Csv => write_csv(spec, outcome),
+			DataFormat::Npy => {
+				let path = output_file(&spec.output.out_base, "npy")?;
+				let values = outcome.samples.iter().flat_map(sample_row).collect();
+				let array = NpyArray::rows(outcome.samples.len(), CSV_COLUMNS.len(), values).map_err(|e| Error::io(&path, e))?;
+				write_npy(&path, &array)?;
+				Ok(path)
+			}
+			DataFormat::Npz => {
+				let path = output_file(&spec.output.out_base, "npz")?;
+				let column = |f: fn(&SamplePoint) -> f64| NpyArray::f64(outcome.samples.iter().map(f).collect());
+				let arrays = [
+					("period", NpyArray::i64(outcome.samples.iter().map(|s| s.period as i64).collect())),
+					("t", column(|s| s.t)),
+					("theta_wrapped", column(|s| wrap_angle_pi(s.theta_unwrapped))),
+					("theta_unwrapped", column(|s| s.theta_unwrapped)),
+					("omega", column(|s| s.omega)),
+				];
+				write_npz(&path, &arrays)?;
+				Ok(path)
+			}
+			DataFormat::Ppb => {
+				let path = output_file(&spec.output.out_base, "ppb")?;
+				write_container(&path, spec, outcome, spec.output.compress)?;
+				Ok(path)
+			}
+		})
+		.collect()
+}
+
+fn sample_row(s: &SamplePoint) -> [f64; 5] {
+	[s.period as f64, s.t, wrap_angle_pi(s.theta_unwrapped), s.theta_unwrapped, s.omega]
+}
+
+pub fn write_csv(spec: &RunSpec, outcome: &RunOutcome) -> Result<PathBuf> {
+	let path = output_file(&spec.output.out_base, "csv")?;
+	let file = File::create(&path).map_err(|e| Error::io(&path, e))?;
+	write_csv_to(BufWriter::new(file), spec, outcome).map_err(|e| Error::io(&path, e))?;
+	Ok(path)
+}
+
+pub(crate) fn write_csv_to(mut out: impl Write, spec: &RunSpec, outcome: &RunOutcome) -> std::io::Result<()> {
+	for line in metadata_lines(spec, outcome) {
+		writeln!(out, "# {line}")?;
+	}
+	writeln!(out, "{}", CSV_COLUMNS.join(","))?;
+	for s in &outcome.samples {
+		let [period, t, theta_wrapped, theta_unwrapped, omega] = sample_row(s);
+		writeln!(out, "{period},{t},{theta_wrapped},{theta_unwrapped},{omega}")?;
+	}
+	out.flush()
+}
+
+pub(crate) fn metadata_lines(spec: &RunSpec, outcome: &RunOutcome) -> Vec<String> {
+	let mut lines = vec![
+		format!("{} {} Poincaré samples", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+		format!("samples: {}{}", outcome.samples.len(), if outcome.cancelled { " (cancelled)" } else { "" }),
+		"spec:".to_string(),
+	];
+	let spec = serde_json::to_string_pretty(spec).expect("run spec serialises to JSON");
+	lines.extend(spec.lines().map(str::to_string));
+	lines
+}
+
+// NumPy formats only; the finite-time exponents are already summarised in the analyze report.
+pub fn write_ftle(spec: &RunSpec, ftle: &[f64]) -> Result<Vec<PathBuf>> {
+	let out_base = format!("{}__ftle", spec.output.out_base);
+	let first_period = spec.integrator.n_periods_warmup as i64 + 1;
+	let mut written = Vec::new();
+	for format in &spec.output.data {
+		match format {
+			DataFormat::Csv | DataFormat::Ppb => {}
+			DataFormat::Npy => {
+				let path = output_file(&out_base, "npy")?;
+				write_npy(&path, &NpyArray::f64(ftle.to_vec()))?;
+				written.push(path);
+			}
+			DataFormat::Npz => {
+				let path = output_file(&out_base, "npz")?;
+				let periods = (0..ftle.len() as i64).map(|i| first_period + i).collect();
+				write_npz(&path, &[("period", NpyArray::i64(periods)), ("ftle", NpyArray::f64(ftle.to_vec()))])?;
+				written.push(path);
+			}
+		}
+	}
+	Ok(written)
+}
+
+// One row per run, one column per swept field in `sweeps[0]` order.
+pub fn write_sweep_arrays(base: &str, formats: &[DataFormat], sweeps: &[Vec<SweepValue>]) -> Result<Vec<PathBuf>> {
+	let out_base = format!("{base}__sweep");
+	let axes: Vec<&str> = sweeps.first().map_or(Vec::new(), |s| s.iter().map(|v| v.path.as_str()).collect());
+	let mut written = Vec::new();
+	for format in formats {
+		match format {
+			DataFormat::Csv | DataFormat::Ppb => {}
+			DataFormat::Npy => {
+				let path = output_file(&out_base, "npy")?;
+				let values = sweeps.iter().flat_map(|run| run.iter().map(|v| v.value)).collect();
+				let array = NpyArray::rows(sweeps.len(), axes.len(), values).map_err(|e| Error::io(&path, e))?;
+				write_npy(&path, &array)?;
+				written.push(path);
+			}
+			DataFormat::Npz => {
+				let path = output_file(&out_base, "npz")?;
+				let arrays: Vec<(&str, NpyArray)> = axes
+					.iter()
+					.enumerate()
+					.map(|(i, axis)| (*axis, NpyArray::f64(sweeps.iter().map(|run| run[i].value).collect())))
+					.collect();
+				write_npz(&path, &arrays)?;
+				written.push(path);
+			}
+		}
+	}
+	Ok(written)
+}
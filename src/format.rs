@@ -0,0 +1,6 @@
+// Digits after the decimal point in the shortest representation of `v`, so labels derived from a
+// step print no more precision than the step itself was written with.
+pub(crate) fn decimal_places(v: f64) -> usize {
+	let text = format!("{v}");
+	text.split_once('.').map_or(0, |(_, frac)| frac.len())
+}
@@ -1,5 +1,5 @@
 use crate::types::*;
-use std::f64::{consts::PI, EPSILON};
+use std::f64::consts::PI;
 
 pub trait Stepper {
     fn step(&mut self, s: State, phys: &PhysicalParams, dt: f64) -> State;
@@ -19,6 +19,7 @@ impl Stepper for EulerCromer {
 }
 
 pub trait AdaptiveStepper {
+    #[allow(clippy::too_many_arguments)]
     fn advance_to(
         &mut self,
         s: State,
@@ -30,9 +31,14 @@ pub trait AdaptiveStepper {
         dt_min: f64,
         dt_max: f64,
     ) -> (State, f64);
+
+    fn stats(&self) -> IntegratorStats;
 }
 
-pub struct RK45;
+#[derive(Default)]
+pub struct RK45 {
+    pub stats: IntegratorStats,
+}
 
 impl AdaptiveStepper for RK45 {
     fn advance_to(
@@ -46,7 +52,7 @@ impl AdaptiveStepper for RK45 {
         dt_min: f64,
         dt_max: f64,
     ) -> (State, f64) {
-        if t_target <= s.t + EPSILON {
+        if t_target <= s.t + f64::EPSILON {
             return (State { t: t_target, ..s }, dt_init.max(dt_min));
         }
         let safety = 0.9;
@@ -58,7 +64,7 @@ impl AdaptiveStepper for RK45 {
             h = dt_min.max(t_target - state.t);
         }
         let mut last_h = h;
-        while t_target - state.t > EPSILON {
+        while t_target - state.t > f64::EPSILON {
             let remaining = t_target - state.t;
             let mut h_trial = h.min(dt_max);
             if h_trial > remaining {
@@ -177,8 +183,9 @@ impl AdaptiveStepper for RK45 {
                     atol,
                 );
                 if err <= 1.0 || h_trial <= dt_min {
+                    self.stats.accept(h_trial);
                     state = State { t: state.t + h_trial, theta: theta5, omega: omega5 };
-                    if t_target - state.t <= EPSILON {
+                    if t_target - state.t <= f64::EPSILON {
                         state.t = t_target;
                     }
                     last_h = h_trial;
@@ -193,12 +200,13 @@ impl AdaptiveStepper for RK45 {
                     }
                     break;
                 } else {
+                    self.stats.reject();
                     let factor = (safety * err.powf(-0.2)).clamp(min_factor, 1.0);
                     let mut new_h = h_trial * factor;
                     if new_h < dt_min && remaining > dt_min {
                         new_h = dt_min;
                     }
-                    if new_h <= EPSILON {
+                    if new_h <= f64::EPSILON {
                         new_h = remaining;
                     }
                     h_trial = new_h.min(remaining);
@@ -208,9 +216,16 @@ impl AdaptiveStepper for RK45 {
         state.t = t_target;
         (state, last_h)
     }
+
+    fn stats(&self) -> IntegratorStats {
+        self.stats
+    }
 }
 
-pub struct BulirschStoer;
+#[derive(Default)]
+pub struct BulirschStoer {
+    pub stats: IntegratorStats,
+}
 
 impl BulirschStoer {
     fn modified_midpoint(state: &State, phys: &PhysicalParams, h: f64, n: usize) -> (f64, f64) {
@@ -250,7 +265,7 @@ impl AdaptiveStepper for BulirschStoer {
         dt_min: f64,
         dt_max: f64,
     ) -> (State, f64) {
-        if t_target <= s.t + EPSILON {
+        if t_target <= s.t + f64::EPSILON {
             return (State { t: t_target, ..s }, dt_init.max(dt_min));
         }
         let safety = 0.9;
@@ -263,7 +278,7 @@ impl AdaptiveStepper for BulirschStoer {
         }
         let mut last_h = h;
         let n_seq = [2usize, 4, 6, 8, 10, 12];
-        while t_target - state.t > EPSILON {
+        while t_target - state.t > f64::EPSILON {
             let remaining = t_target - state.t;
             let mut h_trial = h.min(dt_max);
             if h_trial > remaining {
@@ -317,8 +332,9 @@ impl AdaptiveStepper for BulirschStoer {
                     }
                 }
                 if accepted {
+                    self.stats.accept(h_trial);
                     state = State { t: state.t + h_trial, theta: best.0, omega: best.1 };
-                    if t_target - state.t <= EPSILON {
+                    if t_target - state.t <= f64::EPSILON {
                         state.t = t_target;
                     }
                     last_h = h_trial;
@@ -333,6 +349,7 @@ impl AdaptiveStepper for BulirschStoer {
                     }
                     break;
                 } else {
+                    self.stats.reject();
                     let factor = if err <= 1e-12 {
                         min_factor
                     } else {
@@ -342,7 +359,7 @@ impl AdaptiveStepper for BulirschStoer {
                     if new_h < dt_min && remaining > dt_min {
                         new_h = dt_min;
                     }
-                    if new_h <= EPSILON {
+                    if new_h <= f64::EPSILON {
                         new_h = remaining;
                     }
                     h_trial = new_h.min(remaining);
@@ -353,6 +370,10 @@ impl AdaptiveStepper for BulirschStoer {
         state.t = t_target;
         (state, last_h)
     }
+
+    fn stats(&self) -> IntegratorStats {
+        self.stats
+    }
 }
 
 impl Stepper for RK4 {
@@ -402,16 +423,8 @@ pub fn derive_dt_and_k(phys: &PhysicalParams, integ: &IntegratorParams) -> (f64,
     }
 }
 
-pub fn steps_for_warmup(integ: &IntegratorParams, k: usize) -> usize {
-    integ.n_periods_warmup.saturating_mul(k)
-}
-
-pub fn steps_for_sampling(integ: &IntegratorParams, k: usize) -> usize {
-    integ.n_periods_samples.saturating_mul(k)
-}
-
 fn snap_even(x: usize) -> usize {
-    let lower = if x % 2 == 0 { x } else { x.saturating_sub(1) };
+    let lower = if x.is_multiple_of(2) { x } else { x.saturating_sub(1) };
     let higher = lower.checked_add(2).unwrap_or(lower);
     let dist_lower = x.saturating_sub(lower);
     let dist_higher = higher.saturating_sub(x);
@@ -428,6 +441,7 @@ fn rhs(theta: f64, omega: f64, t: f64, phys: &PhysicalParams) -> (f64, f64) {
     (dtheta, domega)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn error_norm(
     diff_theta: f64,
     diff_omega: f64,
@@ -1,14 +1,52 @@
+// The run spec schema is one large json! literal.
+#![recursion_limit = "256"]
+
+pub mod analysis;
+pub mod batch;
+pub mod colour;
+pub mod diagnostics;
+pub mod error;
+pub mod format;
+pub mod schema;
 pub mod settings;
 pub mod types;
 pub mod dynamics;
 pub mod integrator;
 pub mod sampling;
 pub mod simulate;
+pub mod output;
+pub mod export;
+pub mod npy;
+pub mod container;
+pub mod provenance;
+pub mod saved;
 pub mod plot;
+pub mod progress;
+#[cfg(test)]
+mod test_support;
 
-pub use settings::load_run_spec;
-pub use simulate::run;
-pub use plot::{save_all, save_all_x};
+pub use error::{Error, Result};
+pub use analysis::{analyze, point_values, Analysis};
+pub use batch::{expand_sweeps, load_run_specs, run_batch, sweep_suffix, SweepValue};
+pub use diagnostics::{Diagnostic, Severity};
+pub use schema::run_spec_schema;
+pub use settings::{
+	apply_override,
+	explain,
+	load_run_spec,
+	load_run_spec_with_diagnostics,
+	validate_run_spec,
+	DefaultedValue,
+	LoadedSpec,
+	SpecFormat,
+};
+pub use simulate::{run, Control, NoObserver, Progress, RunObserver, RunOutcome};
+pub use progress::ConsoleProgress;
+pub use provenance::write_meta;
+pub use saved::{load_saved_run, SavedRun};
+pub use container::{read_container, write_container, Container};
+pub use export::{write_csv, write_data, write_ftle, write_sweep_arrays};
+pub use plot::{save_all, save_all_x, save_all_xy, save_region, save_tiles, Series};
 
 pub use types::RunSpec;
 pub use types::PhysicalParams;
@@ -16,7 +54,22 @@ pub use types::IntegratorParams;
 pub use types::InitialState;
 pub use types::PoincareConfig;
 pub use types::OutputConfig;
+pub use types::DataFormat;
 pub use types::PlotView;
+pub use types::Aspect;
+pub use types::Theme;
+pub use types::Region;
+pub use types::TilePyramid;
+pub use types::TickLabels;
+pub use types::RenderMode;
+pub use types::SvgPoints;
+pub use types::Colormap;
+pub use types::DensityScale;
+pub use types::ColourBy;
+pub use types::MarkerShape;
+pub use types::SeriesStyle;
+pub use types::LegendPosition;
 pub use types::State;
 pub use types::SamplePoint;
+pub use types::IntegratorStats;
 pub use types::IntegratorMethod;
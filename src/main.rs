@@ -1,14 +1,325 @@
-use pendulum_poincare::{load_run_spec, run, save_all, save_all_x};
-
-fn main() {
-	let mut args = std::env::args();
-	let _ = args.next();
-	let path = args.next().unwrap_or_else(|| "run.json".to_string());
-	let spec = load_run_spec(&path);
-	let samples = run(&spec);
-	let points: Vec<(f64, f64)> = samples.iter().map(|s| (s.theta, s.omega)).collect();
-	save_all(&points, &spec.plot, &spec.output.out_base);
-	let points_roi: Vec<(f64, f64)> = points.iter().copied().filter(|(theta, _)| *theta > 2.0).collect();
-	let roi_base = format!("{}__theta_gt_2", spec.output.out_base);
-	save_all_x(&points_roi, &spec.plot, &roi_base, 1.9, 3.3);
+mod cli;
+
+use cli::{Cli, Command};
+use pendulum_poincare::{
+	analyze,
+	apply_override,
+	explain,
+	load_saved_run,
+	load_run_specs,
+	point_values,
+	run_batch,
+	run_spec_schema,
+	save_all,
+	save_region,
+	save_tiles,
+	sweep_suffix,
+	write_data,
+	write_ftle,
+	write_meta,
+	write_sweep_arrays,
+	ColourBy,
+	ConsoleProgress,
+	Error,
+	LoadedSpec,
+	Result,
+	RunObserver,
+	RunOutcome,
+	RunSpec,
+	Series,
+};
+use pendulum_poincare::output::output_file;
+use pendulum_poincare::settings::{load_spec_value, resolve_run_spec};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+fn main() -> ExitCode {
+	let cli = match cli::parse(std::env::args().skip(1)) {
+		Ok(cli) => cli,
+		Err(message) => {
+			eprintln!("error: {message}\n\n{}", cli::USAGE);
+			return ExitCode::from(2);
+		}
+	};
+	match dispatch(&cli) {
+		Ok(code) => code,
+		Err(e) => {
+			eprintln!("error: {e}");
+			ExitCode::FAILURE
+		}
+	}
+}
+
+fn dispatch(cli: &Cli) -> Result<ExitCode> {
+	match cli.command {
+		Command::Help => {
+			println!("{}", cli::USAGE);
+			Ok(ExitCode::SUCCESS)
+		}
+		Command::Schema => {
+			print_json(&run_spec_schema());
+			Ok(ExitCode::SUCCESS)
+		}
+		Command::Validate => validate(cli),
+		Command::Plot if !cli.data.is_empty() => replot(cli),
+		Command::Run | Command::Plot | Command::Sweep => simulate(cli),
+		Command::Analyze => analyze_specs(cli),
+	}
+}
+
+fn validate(cli: &Cli) -> Result<ExitCode> {
+	let loaded = match load_run_specs(cli.spec(), &cli.overrides) {
+		Ok(loaded) => loaded,
+		Err(Error::InvalidSpec(diagnostics)) => {
+			for diagnostic in &diagnostics {
+				eprintln!("{diagnostic}");
+			}
+			return Ok(ExitCode::FAILURE);
+		}
+		Err(e) => return Err(e),
+	};
+	if cli.print_effective {
+		print_effective(&loaded);
+	} else {
+		report_diagnostics(&loaded);
+		eprintln!("{}: ok ({} run{})", cli.spec(), loaded.len(), if loaded.len() == 1 { "" } else { "s" });
+	}
+	Ok(ExitCode::SUCCESS)
+}
+
+fn simulate(cli: &Cli) -> Result<ExitCode> {
+	let loaded = load_run_specs(cli.spec(), &cli.overrides)?;
+	if cli.print_effective {
+		print_effective(&loaded);
+		return Ok(ExitCode::SUCCESS);
+	}
+	report_diagnostics(&loaded);
+	if cli.command == Command::Sweep {
+		if loaded.iter().all(|l| l.sweep.is_empty()) {
+			eprintln!("error: sweep needs at least one list or range value in the spec or via --vary");
+			return Ok(ExitCode::from(2));
+		}
+		write_sweep_index(&loaded)?;
+	}
+	let specs: Vec<RunSpec> = loaded.into_iter().map(|l| l.spec).collect();
+	if specs.len() > 1 {
+		eprintln!("running {} parameter combinations", specs.len());
+	}
+	let cancel = install_cancel_handler();
+	let mut progress = ConsoleProgress::new(Arc::clone(&cancel));
+	let with_data = cli.command != Command::Plot;
+	let results = run_batch(&specs, &mut progress, |spec, outcome, observer| write_outputs(spec, outcome, with_data, &[], &[], observer));
+	progress.finish();
+	if cancel.load(Ordering::SeqCst) {
+		eprintln!("cancelled; outputs contain the samples collected so far");
+	}
+	Ok(report_failures(&specs, results))
+}
+
+fn replot(cli: &Cli) -> Result<ExitCode> {
+	let (data, overlay_paths) = cli.data.split_first().expect("replot is only dispatched with --data");
+	let data = data.as_str();
+	let mut saved = load_saved_run(data)?;
+	let (mut value, source) = match &cli.spec {
+		Some(spec) => (load_spec_value(spec)?, spec.as_str()),
+		None => (saved.spec.clone(), data),
+	};
+	for assignment in &cli.overrides {
+		apply_override(&mut value, assignment)?;
+	}
+	let mut loaded = resolve_run_spec(value, source)?;
+	if cli.spec.is_none() {
+		// The embedded spec is already resolved, so only report what the overrides introduced.
+		let baseline = resolve_run_spec(saved.spec.clone(), data).map(|l| l.diagnostics).unwrap_or_default();
+		loaded.diagnostics.retain(|d| !baseline.contains(d));
+	}
+	// Writing under the source run's base would replace its figures and its provenance record.
+	let source_base = saved.spec.pointer("/output/out_base").and_then(Value::as_str);
+	if source_base == Some(loaded.spec.output.out_base.as_str()) {
+		loaded.spec.output.out_base.push_str("__replot");
+	}
+	if cli.print_effective {
+		print_effective(std::slice::from_ref(&loaded));
+		return Ok(ExitCode::SUCCESS);
+	}
+	report_diagnostics(std::slice::from_ref(&loaded));
+	saved.set_wrap_to_pi(loaded.spec.poincare.wrap_to_pi);
+	let mut samples = saved.outcome.samples.len();
+	// Overlays keep their own physics, for colour_by, but are drawn in the first run's θ convention.
+	let overlays = overlay_paths
+		.iter()
+		.map(|path| {
+			let mut overlay = load_saved_run(path)?;
+			overlay.set_wrap_to_pi(loaded.spec.poincare.wrap_to_pi);
+			samples += overlay.outcome.samples.len();
+			let spec = resolve_run_spec(overlay.spec, path)?.spec;
+			Ok(Overlay { label: file_label(path), spec, outcome: overlay.outcome })
+		})
+		.collect::<Result<Vec<_>>>()?;
+	let meta = output_file(&loaded.spec.output.out_base, "meta.json")?;
+	if records_run(&meta) {
+		let message = "holds the provenance of a run; re-plot under another --set output.out_base";
+		return Err(Error::io(&meta, std::io::Error::new(std::io::ErrorKind::AlreadyExists, message)));
+	}
+	let inputs: Vec<PathBuf> = cli.data.iter().map(PathBuf::from).collect();
+	let cancel = install_cancel_handler();
+	let mut progress = ConsoleProgress::new(Arc::clone(&cancel));
+	let written = write_outputs(&loaded.spec, saved.outcome, false, &inputs, &overlays, &mut progress);
+	progress.finish();
+	written?;
+	eprintln!("re-plotted {samples} samples from {} as {}", cli.data.join(", "), loaded.spec.output.out_base);
+	Ok(ExitCode::SUCCESS)
+}
+
+fn analyze_specs(cli: &Cli) -> Result<ExitCode> {
+	let loaded = load_run_specs(cli.spec(), &cli.overrides)?;
+	report_diagnostics(&loaded);
+	let cancel = install_cancel_handler();
+	let mut reports = Vec::with_capacity(loaded.len());
+	for item in &loaded {
+		let mut progress = ConsoleProgress::new(Arc::clone(&cancel));
+		let analysis = analyze(&item.spec, &mut progress)?;
+		progress.finish();
+		write_ftle(&item.spec, &analysis.ftle)?;
+		reports.push(json!({
+			"out_base": item.spec.output.out_base,
+			"sweep": item.sweep,
+			"analysis": analysis,
+		}));
+		if analysis.cancelled {
+			break;
+		}
+	}
+	let report = if reports.len() == 1 { reports.remove(0) } else { Value::Array(reports) };
+	print_json(&report);
+	Ok(ExitCode::SUCCESS)
+}
+
+// A further saved run drawn over the first one by `plot --data a.ppb --data b.ppb`.
+struct Overlay {
+	label: String,
+	spec: RunSpec,
+	outcome: RunOutcome,
+}
+
+// True for a .meta.json written by a run rather than by an earlier re-plot, which lists its inputs.
+// Records from before re-plotting existed have no `inputs` at all.
+fn records_run(meta: &std::path::Path) -> bool {
+	std::fs::read(meta)
+		.ok()
+		.and_then(|bytes| serde_json::from_slice::<Value>(&bytes).ok())
+		.is_some_and(|meta| meta.get("inputs").and_then(Value::as_array).is_none_or(Vec::is_empty))
+}
+
+fn file_label(path: &str) -> String {
+	let path = std::path::Path::new(path);
+	path.file_stem().unwrap_or(path.as_os_str()).to_string_lossy().into_owned()
+}
+
+fn write_outputs(
+	spec: &RunSpec,
+	outcome: RunOutcome,
+	with_data: bool,
+	inputs: &[PathBuf],
+	overlays: &[Overlay],
+	observer: &mut dyn RunObserver,
+) -> Result<()> {
+	let mut written = Vec::new();
+	if with_data {
+		written.extend(write_data(spec, &outcome)?);
+	}
+	let label = match inputs.first() {
+		Some(path) => file_label(&path.to_string_lossy()),
+		None => spec.output.out_base.clone(),
+	};
+	let runs: Vec<(String, &RunSpec, &RunOutcome)> = std::iter::once((label, spec, &outcome))
+		.chain(overlays.iter().map(|o| (o.label.clone(), &o.spec, &o.outcome)))
+		.collect();
+	let points: Vec<Vec<(f64, f64)>> =
+		runs.iter().map(|(_, _, outcome)| outcome.samples.iter().map(|s| (s.theta, s.omega)).collect()).collect();
+	let mut values = Vec::with_capacity(runs.len());
+	for (label, run_spec, outcome) in &runs {
+		let run_values = point_values(run_spec, spec.plot.colour_by, &outcome.samples, observer)?;
+		if run_values.is_none() && spec.plot.colour_by != ColourBy::None {
+			eprintln!("warning: {label}: cancelled before the colour values were computed; drawn in one colour");
+		}
+		values.push(run_values);
+	}
+	let series: Vec<Series> = runs
+		.iter()
+		.zip(&points)
+		.zip(&values)
+		.map(|(((label, _, _), points), values)| Series { label: label.clone(), points, values: values.as_deref() })
+		.collect();
+	written.extend(save_all(&series, &spec.plot, &spec.output.out_base)?);
+	for region in &spec.plot.regions {
+		written.extend(save_region(&series, &spec.plot, &spec.output.out_base, region)?);
+	}
+	if let Some(pyramid) = &spec.plot.tiles {
+		written.extend(save_tiles(&series, &spec.plot, &spec.output.out_base, pyramid)?);
+	}
+	write_meta(spec, &outcome, inputs, &written)?;
+	Ok(())
+}
+
+fn write_sweep_index(loaded: &[LoadedSpec]) -> Result<()> {
+	let first = &loaded[0];
+	let out_base = &first.spec.output.out_base;
+	let base = out_base.strip_suffix(&sweep_suffix(&first.sweep)).unwrap_or(out_base);
+	let runs: Vec<Value> = loaded
+		.iter()
+		.map(|l| json!({ "out_base": l.spec.output.out_base, "params": l.sweep }))
+		.collect();
+	let path = output_file(&format!("{base}__sweep"), "json")?;
+	let contents = serde_json::to_string_pretty(&json!({ "runs": runs })).expect("sweep index is valid JSON");
+	std::fs::write(&path, contents).map_err(|e| Error::io(&path, e))?;
+	let sweeps: Vec<_> = loaded.iter().map(|l| l.sweep.clone()).collect();
+	write_sweep_arrays(base, &first.spec.output.data, &sweeps)?;
+	Ok(())
+}
+
+fn install_cancel_handler() -> Arc<AtomicBool> {
+	let cancel = Arc::new(AtomicBool::new(false));
+	let handler_flag = Arc::clone(&cancel);
+	let _ = ctrlc::set_handler(move || {
+		if handler_flag.swap(true, Ordering::SeqCst) {
+			std::process::exit(130);
+		}
+	});
+	cancel
+}
+
+fn report_diagnostics(loaded: &[LoadedSpec]) {
+	let mut seen = HashSet::new();
+	for diagnostic in loaded.iter().flat_map(|l| &l.diagnostics) {
+		let line = diagnostic.to_string();
+		if seen.insert(line.clone()) {
+			eprintln!("{line}");
+		}
+	}
+}
+
+fn report_failures(specs: &[RunSpec], results: Vec<Result<()>>) -> ExitCode {
+	let mut code = ExitCode::SUCCESS;
+	for (spec, result) in specs.iter().zip(results) {
+		if let Err(e) = result {
+			eprintln!("error: {}: {e}", spec.output.out_base);
+			code = ExitCode::FAILURE;
+		}
+	}
+	code
+}
+
+fn print_effective(loaded: &[LoadedSpec]) {
+	let mut reports: Vec<Value> = loaded.iter().map(explain).collect();
+	let report = if reports.len() == 1 { reports.remove(0) } else { Value::Array(reports) };
+	print_json(&report);
+}
+
+fn print_json(value: &Value) {
+	println!("{}", serde_json::to_string_pretty(value).expect("value is valid JSON"));
 }
@@ -0,0 +1,244 @@
+use crate::error::{Error, Result};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+// All ones in a size, offset or count field tells readers to look for a zip64 record.
+const ZIP_LIMIT: usize = u32::MAX as usize - 1;
+
+#[derive(Clone, Debug)]
+pub enum NpyData {
+	F64(Vec<f64>),
+	I64(Vec<i64>),
+}
+
+#[derive(Clone, Debug)]
+pub struct NpyArray {
+	pub shape: Vec<usize>,
+	pub data: NpyData,
+}
+
+impl NpyArray {
+	pub fn f64(values: Vec<f64>) -> Self {
+		NpyArray { shape: vec![values.len()], data: NpyData::F64(values) }
+	}
+
+	pub fn i64(values: Vec<i64>) -> Self {
+		NpyArray { shape: vec![values.len()], data: NpyData::I64(values) }
+	}
+
+	pub fn rows(rows: usize, columns: usize, values: Vec<f64>) -> std::io::Result<Self> {
+		if rows.checked_mul(columns) != Some(values.len()) {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::InvalidInput,
+				format!("{} values do not fill a {rows}x{columns} array", values.len()),
+			));
+		}
+		Ok(NpyArray { shape: vec![rows, columns], data: NpyData::F64(values) })
+	}
+
+	fn header(&self) -> Vec<u8> {
+		let descr = match self.data {
+			NpyData::F64(_) => "<f8",
+			NpyData::I64(_) => "<i8",
+		};
+		let shape = match self.shape.as_slice() {
+			[n] => format!("({n},)"),
+			dims => format!("({})", dims.iter().map(usize::to_string).collect::<Vec<_>>().join(", ")),
+		};
+		let mut dict = format!("{{'descr': '{descr}', 'fortran_order': False, 'shape': {shape}, }}");
+		// Magic, version and length take 10 bytes; the whole header is padded to a multiple of 64.
+		let padding = 63 - (MAGIC.len() + 4 + dict.len()) % 64;
+		dict.extend(std::iter::repeat_n(' ', padding));
+		dict.push('\n');
+		let mut header = Vec::with_capacity(10 + dict.len());
+		header.extend_from_slice(MAGIC);
+		header.extend_from_slice(&[1, 0]);
+		header.extend_from_slice(&(dict.len() as u16).to_le_bytes());
+		header.extend_from_slice(dict.as_bytes());
+		header
+	}
+
+	pub fn write_to(&self, out: &mut impl Write) -> std::io::Result<()> {
+		out.write_all(&self.header())?;
+		match &self.data {
+			NpyData::F64(values) => values.iter().try_for_each(|v| out.write_all(&v.to_le_bytes())),
+			NpyData::I64(values) => values.iter().try_for_each(|v| out.write_all(&v.to_le_bytes())),
+		}
+	}
+
+	fn to_bytes(&self) -> Vec<u8> {
+		let mut bytes = Vec::new();
+		self.write_to(&mut bytes).expect("writing to memory cannot fail");
+		bytes
+	}
+}
+
+pub fn write_npy(path: &Path, array: &NpyArray) -> Result<()> {
+	let file = File::create(path).map_err(|e| Error::io(path, e))?;
+	let mut out = BufWriter::new(file);
+	array.write_to(&mut out).and_then(|_| out.flush()).map_err(|e| Error::io(path, e))
+}
+
+// An .npz file is an uncompressed zip archive holding one <name>.npy entry per array.
+pub fn write_npz(path: &Path, arrays: &[(&str, NpyArray)]) -> Result<()> {
+	let file = File::create(path).map_err(|e| Error::io(path, e))?;
+	let mut out = BufWriter::new(file);
+	let mut central = Vec::new();
+	let mut offset = 0usize;
+	let too_large = || Error::io(path, std::io::Error::other("arrays too large for a non-zip64 archive"));
+	for (name, array) in arrays {
+		let entry = format!("{name}.npy");
+		let bytes = array.to_bytes();
+		if bytes.len() > ZIP_LIMIT || offset > ZIP_LIMIT {
+			return Err(too_large());
+		}
+		let crc = crc32(&bytes);
+		let local = zip_header(0x0403_4b50, &entry, crc, bytes.len(), None);
+		out.write_all(&local).and_then(|_| out.write_all(&bytes)).map_err(|e| Error::io(path, e))?;
+		central.extend(zip_header(0x0201_4b50, &entry, crc, bytes.len(), Some(offset)));
+		offset += local.len() + bytes.len();
+	}
+	// The end record stores the central directory's offset and size in 32 bits as well.
+	if offset > ZIP_LIMIT || central.len() > ZIP_LIMIT || arrays.len() >= u16::MAX as usize {
+		return Err(too_large());
+	}
+	let mut end = Vec::with_capacity(22);
+	end.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+	end.extend_from_slice(&[0; 4]);
+	end.extend_from_slice(&(arrays.len() as u16).to_le_bytes());
+	end.extend_from_slice(&(arrays.len() as u16).to_le_bytes());
+	end.extend_from_slice(&(central.len() as u32).to_le_bytes());
+	end.extend_from_slice(&(offset as u32).to_le_bytes());
+	end.extend_from_slice(&[0; 2]);
+	out.write_all(&central)
+		.and_then(|_| out.write_all(&end))
+		.and_then(|_| out.flush())
+		.map_err(|e| Error::io(path, e))
+}
+
+// Local file header when `offset` is None, central directory entry otherwise.
+fn zip_header(signature: u32, name: &str, crc: u32, size: usize, offset: Option<usize>) -> Vec<u8> {
+	let mut header = Vec::with_capacity(46 + name.len());
+	header.extend_from_slice(&signature.to_le_bytes());
+	if offset.is_some() {
+		header.extend_from_slice(&20u16.to_le_bytes());
+	}
+	header.extend_from_slice(&20u16.to_le_bytes());
+	header.extend_from_slice(&[0; 2]);
+	header.extend_from_slice(&[0; 2]);
+	header.extend_from_slice(&[0; 2]);
+	header.extend_from_slice(&0x21u16.to_le_bytes());
+	header.extend_from_slice(&crc.to_le_bytes());
+	header.extend_from_slice(&(size as u32).to_le_bytes());
+	header.extend_from_slice(&(size as u32).to_le_bytes());
+	header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+	header.extend_from_slice(&[0; 2]);
+	if let Some(offset) = offset {
+		header.extend_from_slice(&[0; 6]);
+		header.extend_from_slice(&[0; 4]);
+		header.extend_from_slice(&(offset as u32).to_le_bytes());
+	}
+	header.extend_from_slice(name.as_bytes());
+	header
+}
+
+const CRC_TABLE: [u32; 256] = {
+	let mut table = [0u32; 256];
+	let mut i = 0;
+	while i < 256 {
+		let mut crc = i as u32;
+		let mut bit = 0;
+		while bit < 8 {
+			crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+			bit += 1;
+		}
+		table[i] = crc;
+		i += 1;
+	}
+	table
+};
+
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+	!bytes.iter().fold(!0u32, |crc, &byte| CRC_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn u16_at(bytes: &[u8], at: usize) -> usize {
+		u16::from_le_bytes([bytes[at], bytes[at + 1]]) as usize
+	}
+
+	fn u32_at(bytes: &[u8], at: usize) -> usize {
+		u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) as usize
+	}
+
+	#[test]
+	fn npy_header_layout() {
+		let bytes = NpyArray::rows(2, 3, vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.5]).unwrap().to_bytes();
+		assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+		let dict_len = u16_at(&bytes, 8);
+		assert_eq!((10 + dict_len) % 64, 0);
+		let dict = std::str::from_utf8(&bytes[10..10 + dict_len]).unwrap();
+		assert_eq!(dict.trim_end(), "{'descr': '<f8', 'fortran_order': False, 'shape': (2, 3), }");
+		assert!(dict.ends_with('\n'));
+		let data = &bytes[10 + dict_len..];
+		assert_eq!(data.len(), 6 * 8);
+		assert_eq!(f64::from_le_bytes(data[40..48].try_into().unwrap()), 5.5);
+
+		let bytes = NpyArray::i64(vec![-1, 7]).to_bytes();
+		let dict_len = u16_at(&bytes, 8);
+		let dict = std::str::from_utf8(&bytes[10..10 + dict_len]).unwrap();
+		assert!(dict.starts_with("{'descr': '<i8', 'fortran_order': False, 'shape': (2,), }"));
+		assert_eq!(i64::from_le_bytes(bytes[10 + dict_len..18 + dict_len].try_into().unwrap()), -1);
+	}
+
+	#[test]
+	fn rows_rejects_mismatched_shape() {
+		assert!(NpyArray::rows(2, 3, vec![0.0; 5]).is_err());
+		assert!(NpyArray::rows(usize::MAX, 2, Vec::new()).is_err());
+	}
+
+	#[test]
+	fn crc32_matches_reference() {
+		assert_eq!(crc32(b""), 0);
+		assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+	}
+
+	#[test]
+	fn npz_archive_layout() {
+		let path = std::env::temp_dir().join(format!("pendulum-poincare-test-{}.npz", std::process::id()));
+		let arrays = [("period", NpyArray::i64(vec![1, 2, 3])), ("omega", NpyArray::f64(vec![0.5, -0.25]))];
+		write_npz(&path, &arrays).unwrap();
+		let zip = std::fs::read(&path).unwrap();
+		std::fs::remove_file(&path).unwrap();
+
+		let end = zip.len() - 22;
+		assert_eq!(u32_at(&zip, end), 0x0605_4b50);
+		assert_eq!(u16_at(&zip, end + 8), 2);
+		assert_eq!(u16_at(&zip, end + 10), 2);
+		let (central_size, central_offset) = (u32_at(&zip, end + 12), u32_at(&zip, end + 16));
+		assert_eq!(central_offset + central_size, end);
+
+		let mut entry = central_offset;
+		for (name, array) in &arrays {
+			let expected = array.to_bytes();
+			assert_eq!(u32_at(&zip, entry), 0x0201_4b50);
+			let (crc, size, name_len) = (u32_at(&zip, entry + 16), u32_at(&zip, entry + 20), u16_at(&zip, entry + 28));
+			assert_eq!(&zip[entry + 46..entry + 46 + name_len], format!("{name}.npy").as_bytes());
+			assert_eq!((crc, size), (crc32(&expected) as usize, expected.len()));
+
+			let local = u32_at(&zip, entry + 42);
+			assert_eq!(u32_at(&zip, local), 0x0403_4b50);
+			assert_eq!(u16_at(&zip, local + 8), 0, "entries are stored uncompressed");
+			assert_eq!(u32_at(&zip, local + 14), crc);
+			let data = local + 30 + u16_at(&zip, local + 26);
+			assert_eq!(&zip[data..data + size], expected.as_slice());
+			entry += 46 + name_len;
+		}
+		assert_eq!(entry, end);
+	}
+}
@@ -0,0 +1,10 @@
+use crate::error::{Error, Result};
+use std::path::{Path, PathBuf};
+
+pub const OUTPUT_DIR: &str = "output";
+
+pub fn output_file(out_base: &str, extension: &str) -> Result<PathBuf> {
+	let output_dir = Path::new(OUTPUT_DIR);
+	std::fs::create_dir_all(output_dir).map_err(|e| Error::io(output_dir, e))?;
+	Ok(output_dir.join(format!("{out_base}.{extension}")))
+}
@@ -0,0 +1,54 @@
+use crate::types::Colormap;
+use plotly::common::ColorScaleElement;
+
+// Ten evenly spaced samples of each matplotlib map, interpolated linearly in between.
+const VIRIDIS: [u32; 10] = [0x440154, 0x482878, 0x3e4a89, 0x31688e, 0x26828e, 0x1f9e89, 0x35b779, 0x6dcd59, 0xb4de2c, 0xfde725];
+const MAGMA: [u32; 10] = [0x000004, 0x180f3d, 0x440f76, 0x721f81, 0x9e2f7f, 0xcd4071, 0xf1605d, 0xfd9668, 0xfec98d, 0xfcfdbf];
+const INFERNO: [u32; 10] = [0x000004, 0x1b0c41, 0x4a0c6b, 0x781c6d, 0xa52c60, 0xcf4446, 0xed6925, 0xfb9b06, 0xf7d13d, 0xfcffa4];
+const PLASMA: [u32; 10] = [0x0d0887, 0x46039f, 0x7201a8, 0x9c179e, 0xbd3786, 0xd8576b, 0xed7953, 0xfb9f3a, 0xfdca26, 0xf0f921];
+// Starts at mid grey rather than white so that sparse bins stay visible on the white canvas.
+const GREYS: [u32; 2] = [0xc0c0c0, 0x000000];
+
+// Matplotlib's tab10, for series without a colour of their own.
+pub(crate) const SERIES_PALETTE: [u32; 10] =
+	[0x1f77b4, 0xff7f0e, 0x2ca02c, 0xd62728, 0x9467bd, 0x8c564b, 0xe377c2, 0x7f7f7f, 0xbcbd22, 0x17becf];
+
+fn stops(map: Colormap) -> &'static [u32] {
+	match map {
+		Colormap::Viridis => &VIRIDIS,
+		Colormap::Magma => &MAGMA,
+		Colormap::Inferno => &INFERNO,
+		Colormap::Plasma => &PLASMA,
+		Colormap::Greys => &GREYS,
+	}
+}
+
+fn channels(hex: u32) -> [f64; 3] {
+	[(hex >> 16) as f64, ((hex >> 8) & 0xff) as f64, (hex & 0xff) as f64]
+}
+
+pub(crate) fn css(rgb: [u8; 3]) -> String {
+	let [r, g, b] = rgb;
+	format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+pub(crate) fn rgb(map: Colormap, t: f64) -> [u8; 3] {
+	let stops = stops(map);
+	let position = t.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+	let i = (position.floor() as usize).min(stops.len() - 2);
+	let f = position - i as f64;
+	let (a, b) = (channels(stops[i]), channels(stops[i + 1]));
+	[0, 1, 2].map(|c| (a[c] + (b[c] - a[c]) * f).round() as u8)
+}
+
+pub(crate) fn plotly_scale(map: Colormap) -> Vec<ColorScaleElement> {
+	let stops = stops(map);
+	stops
+		.iter()
+		.enumerate()
+		.map(|(i, &hex)| {
+			let [r, g, b] = channels(hex);
+			ColorScaleElement(i as f64 / (stops.len() - 1) as f64, format!("rgb({r},{g},{b})"))
+		})
+		.collect()
+}
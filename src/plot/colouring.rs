@@ -0,0 +1,56 @@
+use super::colormap::rgb;
+use super::ticks::axis_ticks;
+use crate::types::{ColourBy, Colormap, TickLabels};
+
+// Share of local FTLE values left off each end of the colour scale, so that a few near-singular
+// periods do not flatten the rest into one colour.
+const FTLE_CLIP: f64 = 0.02;
+// Colours are quantised to as many levels as the colourbar has bands, which keeps SVG paths few.
+const LEVELS: f64 = 255.0;
+
+// Where per-sample values fall on the colour scale, and the colourbar that explains them.
+pub(crate) struct Colouring {
+	pub min: f64,
+	pub max: f64,
+	pub title: &'static str,
+	// (value, label) pairs within [min, max].
+	pub ticks: Vec<(f64, String)>,
+}
+
+impl Colouring {
+	pub fn new(by: ColourBy, values: &[f64]) -> Option<Self> {
+		let mut finite: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+		finite.sort_unstable_by(f64::total_cmp);
+		let quantile = |q: f64| finite.get(((finite.len().max(1) - 1) as f64 * q).round() as usize).copied().unwrap_or(0.0);
+		let (min, max, title) = match by {
+			ColourBy::None => return None,
+			ColourBy::Period => (quantile(0.0), quantile(1.0), "drive period"),
+			ColourBy::Energy => (quantile(0.0), quantile(1.0), "energy (J/kg)"),
+			ColourBy::Ftle => (quantile(FTLE_CLIP), quantile(1.0 - FTLE_CLIP), "local FTLE (1/s)"),
+			ColourBy::Rotation => (-1.0, 1.0, "rotation"),
+		};
+		let (min, max) = if max > min { (min, max) } else { (min - 0.5, min + 0.5) };
+		let ticks = match by {
+			ColourBy::Rotation => vec![(-1.0, "cw".to_string()), (0.0, "0".to_string()), (1.0, "ccw".to_string())],
+			_ => {
+				let ticks = axis_ticks(min, max, None, TickLabels::Numeric);
+				ticks.values.into_iter().zip(ticks.labels).collect()
+			}
+		};
+		Some(Self { min, max, title, ticks })
+	}
+
+	// Position of a value on the colour scale in [0, 1], clamped at the ends.
+	pub fn level(&self, value: f64) -> f64 {
+		let level = (value - self.min) / (self.max - self.min);
+		if level.is_nan() { 0.0 } else { level.clamp(0.0, 1.0) }
+	}
+
+	pub fn colours(&self, map: Colormap, values: &[f64]) -> Vec<[u8; 3]> {
+		values.iter().map(|&v| rgb(map, (self.level(v) * LEVELS).round() / LEVELS)).collect()
+	}
+
+	pub fn level_ticks(&self) -> Vec<(f64, String)> {
+		self.ticks.iter().map(|(value, label)| (self.level(*value), label.clone())).collect()
+	}
+}
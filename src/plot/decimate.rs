@@ -0,0 +1,56 @@
+use std::collections::HashSet;
+
+// Keeps the first point in each cell of a grid over the plot area, starting from one cell per pixel
+// and doubling the cell size until at most `budget` points remain. Points outside the axes are dropped.
+// Returns the indices of the kept points, in order.
+pub(crate) fn decimate(
+	points: &[(f64, f64)],
+	(x_min, x_max): (f64, f64),
+	(y_min, y_max): (f64, f64),
+	(width, height): (usize, usize),
+	budget: usize,
+) -> Vec<usize> {
+	let visible: Vec<usize> = (0..points.len())
+		.filter(|&i| (x_min..=x_max).contains(&points[i].0) && (y_min..=y_max).contains(&points[i].1))
+		.collect();
+	let (sx, sy) = (width as f64 / (x_max - x_min), height as f64 / (y_max - y_min));
+	let mut cell = 1.0;
+	loop {
+		let mut seen = HashSet::new();
+		let kept: Vec<usize> = visible
+			.iter()
+			.copied()
+			.filter(|&i| {
+				let (x, y) = points[i];
+				seen.insert((((x - x_min) * sx / cell) as u64, ((y_max - y) * sy / cell) as u64))
+			})
+			.collect();
+		if kept.len() <= budget || kept.len() <= 1 {
+			return kept;
+		}
+		cell *= 2.0;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn honours_the_budget() {
+		let points: Vec<(f64, f64)> = (0..20_000).map(|i| ((i as f64 * 0.37).sin() * 4.5, (i as f64 * 0.11).cos() * 2.0)).collect();
+		let area = (600, 400);
+		let inside = |&i: &usize| (-4.0..=4.0).contains(&points[i].0);
+		let everything = decimate(&points, (-4.0, 4.0), (-2.5, 2.5), area, usize::MAX);
+		assert!(everything.iter().all(inside));
+		assert!(everything.len() < points.iter().filter(|p| (-4.0..=4.0).contains(&p.0)).count());
+		for budget in [5000, 1000, 37, 1] {
+			let kept = decimate(&points, (-4.0, 4.0), (-2.5, 2.5), area, budget);
+			assert!(!kept.is_empty() && kept.len() <= budget, "{} points for a budget of {budget}", kept.len());
+			assert!(kept.windows(2).all(|pair| pair[0] < pair[1]));
+			assert!(kept.iter().all(|i| everything.contains(i)));
+		}
+		assert_eq!(decimate(&points, (-4.0, 4.0), (-2.5, 2.5), area, 0).len(), 1);
+		assert!(decimate(&[], (-4.0, 4.0), (-2.5, 2.5), area, 10).is_empty());
+	}
+}
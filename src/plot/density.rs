@@ -0,0 +1,108 @@
+use super::colormap::rgb;
+use super::ticks::axis_ticks;
+use crate::types::{Colormap, DensityScale, TickLabels};
+use rayon::prelude::*;
+use std::ops::Range;
+
+// Counts per bin for a window of rows, row 0 at the top so the buffer can be blitted as an image.
+// `max` is the fullest bin of the whole histogram so that windows of it share one colour scale.
+pub(crate) struct Density {
+	width: usize,
+	counts: Vec<u32>,
+	max: u32,
+}
+
+impl Density {
+	// `max` comes from `fullest` when `rows` is only part of the histogram; None takes it from the
+	// counts, which then must cover every row.
+	pub fn bin(
+		points: &[(f64, f64)],
+		x_range: (f64, f64),
+		y_range: (f64, f64),
+		(width, height): (usize, usize),
+		rows: Range<usize>,
+		max: Option<u32>,
+	) -> Self {
+		debug_assert!(max.is_some() || rows.len() == height, "a window of the histogram needs the overall max");
+		let mut counts = vec![0u32; width * rows.len()];
+		for (row, col) in cells(points, x_range, y_range, (width, height)).filter(|(row, _)| rows.contains(row)) {
+			counts[(row - rows.start) * width + col] += 1;
+		}
+		let max = max.unwrap_or_else(|| counts.iter().copied().max().unwrap_or(0));
+		Self { width, counts, max }
+	}
+
+	// The count in the fullest bin, found from the sorted bin indices so that the whole histogram is
+	// never held in memory.
+	pub fn fullest(points: &[(f64, f64)], x_range: (f64, f64), y_range: (f64, f64), (width, height): (usize, usize)) -> u32 {
+		let mut bins: Vec<usize> = cells(points, x_range, y_range, (width, height)).map(|(row, col)| row * width + col).collect();
+		bins.par_sort_unstable();
+		bins.chunk_by(|a, b| a == b).map(|run| run.len() as u32).max().unwrap_or(0)
+	}
+
+	// Position of a count on the colour scale in [0, 1]; empty bins have none.
+	pub fn level(&self, count: u32, scale: DensityScale) -> Option<f64> {
+		if count == 0 {
+			return None;
+		}
+		if self.max <= 1 {
+			return Some(1.0);
+		}
+		Some(match scale {
+			DensityScale::Log => (count as f64).ln() / (self.max as f64).ln(),
+			DensityScale::Linear => count as f64 / self.max as f64,
+		})
+	}
+
+	// Empty bins take the `empty` colour, the chart background.
+	pub fn raster(&self, map: Colormap, scale: DensityScale, empty: [u8; 3]) -> Vec<u8> {
+		self.counts
+			.iter()
+			.flat_map(|&count| self.level(count, scale).map_or(empty, |t| rgb(map, t)))
+			.collect()
+	}
+
+	pub fn rows_bottom_up(&self, scale: DensityScale) -> Vec<Vec<Option<f64>>> {
+		self.counts
+			.chunks(self.width)
+			.rev()
+			.map(|row| row.iter().map(|&count| self.level(count, scale)).collect())
+			.collect()
+	}
+
+	// Colourbar ticks as (level, label): decades for the log scale, whole counts for the linear one.
+	pub fn colourbar_ticks(&self, scale: DensityScale) -> Vec<(f64, String)> {
+		let max = self.max.max(1);
+		match scale {
+			DensityScale::Log => std::iter::successors(Some(1u64), |v| v.checked_mul(10))
+				.take_while(|&v| v <= max as u64)
+				.map(|v| (self.level(v as u32, scale).unwrap_or(0.0), v.to_string()))
+				.collect(),
+			DensityScale::Linear => {
+				let ticks = axis_ticks(0.0, max as f64, None, TickLabels::Numeric);
+				ticks
+					.values
+					.iter()
+					.zip(ticks.labels)
+					.filter(|(v, _)| v.fract() == 0.0 && **v >= 1.0 && **v <= max as f64)
+					.map(|(v, label)| (self.level(*v as u32, scale).unwrap_or(0.0), label))
+					.collect()
+			}
+		}
+	}
+}
+
+// The (row, column) bin of every point inside the ranges.
+fn cells(
+	points: &[(f64, f64)],
+	(x_min, x_max): (f64, f64),
+	(y_min, y_max): (f64, f64),
+	(width, height): (usize, usize),
+) -> impl Iterator<Item = (usize, usize)> + '_ {
+	let (sx, sy) = (width as f64 / (x_max - x_min), height as f64 / (y_max - y_min));
+	points.iter().filter(move |(x, y)| (x_min..=x_max).contains(x) && (y_min..=y_max).contains(y)).map(move |&(x, y)| {
+		let col = (((x - x_min) * sx) as usize).min(width - 1);
+		let row = (((y_max - y) * sy) as usize).min(height - 1);
+		(row, col)
+	})
+}
@@ -0,0 +1,944 @@
+use crate::colour::{hex_rgb, parse_colour};
+use crate::error::{Error, Result};
+use crate::output::output_file;
+use crate::settings::{max_canvas_height, png_strip_rows, MIN_SIDE_PX};
+use crate::types::*;
+use colormap::{css, plotly_scale, rgb, SERIES_PALETTE};
+use colouring::Colouring;
+use decimate::decimate;
+use density::Density;
+use raster::{project, splat, Projected};
+use strip::write_png_strips;
+use svg::{marker_path, replace_placeholder, MARKER_PLACEHOLDER};
+use plotly::color::Color as PlotlyColor;
+use plotly::common::{Anchor, ColorBar, ColorScale, Font, Marker, MarkerSymbol, Mode, Title};
+use plotly::layout::{Axis, Legend, Margin};
+use plotly::{HeatMap, Layout, Plot, Scatter};
+use plotters::coord::Shift;
+use plotters::drawing::DrawingAreaErrorKind;
+use plotters::prelude::*;
+use plotters::style::text_anchor::{HPos, Pos, VPos};
+use serde::Serialize;
+use std::cell::OnceCell;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use ticks::{axis_ticks, Ticks};
+
+pub use tiles::save_tiles;
+
+mod colormap;
+mod colouring;
+mod decimate;
+mod density;
+mod raster;
+mod strip;
+mod svg;
+mod tiles;
+mod ticks;
+
+const STATIC_MARGIN: i32 = 20;
+const COLOURBAR_GAP: i32 = 20;
+const COLOURBAR_WIDTH: i32 = 30;
+const COLOURBAR_BANDS: i32 = 256;
+// Beyond this many markers the HTML switches to plotly's WebGL scatter, which stays interactive.
+const WEBGL_MIN_POINTS: usize = 10_000;
+// Plotly's heatmap ships every bin to the browser, so keep it far below the static resolution.
+const MAX_HTML_BINS: usize = 1000;
+const DARK_BACKGROUND: [u8; 3] = [0x12, 0x12, 0x12];
+const DARK_FOREGROUND: [u8; 3] = [0xe6, 0xe6, 0xe6];
+
+
+// Canvas size for a chart whose axes, labels and margins take `chrome` pixels across and down.
+// With `aspect: equal` the plot area spans as many pixels per unit of ω as per unit of θ, within
+// the limit `max_canvas_height` sets.
+fn canvas_size(view: &PlotView, (chrome_w, chrome_h): (u32, u32), (x_min, x_max): (f64, f64), (y_min, y_max): (f64, f64)) -> (u32, u32) {
+	let width = view.canvas_width();
+	let height = view.canvas_height().unwrap_or_else(|| {
+		let plot_width = width.saturating_sub(chrome_w) as f64;
+		let plot_height = (plot_width * (y_max - y_min) / (x_max - x_min)).round() as u32;
+		// A θ range far wider than the ω range would otherwise leave no room for the axes.
+		plot_height.saturating_add(chrome_h).max(MIN_SIDE_PX).min(max_canvas_height(view))
+	});
+	(width, height)
+}
+
+fn static_canvas(view: &PlotView, x_range: (f64, f64), y_range: (f64, f64)) -> (u32, u32) {
+	let margin = scaled(view, STATIC_MARGIN);
+	// Plotters sets the caption above the chart with up to 5 pixels of padding either side.
+	let title_font = title_font_px(view);
+	let text_h = (font_family(view), title_font).into_font().box_size(&view.title).map_or(title_font, |(_, h)| h as i32);
+	let caption = text_h + (text_h / 2).min(5) * 2;
+	let chrome_w = margin + left_label_area(view) as i32 + static_margin_right(view);
+	let chrome_h = margin * 2 + caption + bottom_label_area(view) as i32;
+	canvas_size(view, (chrome_w.max(0) as u32, chrome_h.max(0) as u32), x_range, y_range)
+}
+
+fn html_canvas(view: &PlotView, x_range: (f64, f64), y_range: (f64, f64)) -> (u32, u32) {
+	let chrome_w = left_label_area(view) as usize + right_margin(view);
+	let chrome_h = top_margin(view) + bottom_label_area(view) as usize;
+	canvas_size(view, (chrome_w as u32, chrome_h as u32), x_range, y_range)
+}
+
+// A length given in CSS pixels, in canvas pixels at `plot.dpi`.
+fn scaled(view: &PlotView, px: i32) -> i32 {
+	(px as f64 * view.dpi_scale()).round() as i32
+}
+
+fn line_width(view: &PlotView, px: i32) -> u32 {
+	scaled(view, px).max(1) as u32
+}
+
+fn frame_width(view: &PlotView) -> u32 {
+	line_width(view, view.frame_width.unwrap_or(2) as i32)
+}
+
+fn font_family(view: &PlotView) -> &str {
+	view.font_family.as_deref().unwrap_or("sans-serif")
+}
+
+fn x_label(view: &PlotView) -> &str {
+	view.x_label.as_deref().unwrap_or("θ (radians)")
+}
+
+fn y_label(view: &PlotView) -> &str {
+	view.y_label.as_deref().unwrap_or("ω (radians/s)")
+}
+
+// Page colour behind the chart and in empty parts of the plot area.
+fn background(view: &PlotView) -> [u8; 3] {
+	let default = match view.theme {
+		Theme::Light => [255, 255, 255],
+		Theme::Dark => DARK_BACKGROUND,
+	};
+	view.background.as_deref().and_then(parse_colour).unwrap_or(default)
+}
+
+// Colour of text, axes, frame and the lone series' markers.
+fn foreground(view: &PlotView) -> [u8; 3] {
+	let default = match view.theme {
+		Theme::Light => [0, 0, 0],
+		Theme::Dark => DARK_FOREGROUND,
+	};
+	view.foreground.as_deref().and_then(parse_colour).unwrap_or(default)
+}
+
+fn colour([r, g, b]: [u8; 3]) -> RGBColor {
+	RGBColor(r, g, b)
+}
+
+// Marker opacity as a coverage factor; opaque unless `plot.marker_opacity` says otherwise.
+fn marker_alpha(view: &PlotView) -> u8 {
+	(view.marker_opacity.unwrap_or(1.0).clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn data_y_range(series: &[Series<'_>]) -> (f64, f64) {
+	let mut ys = series.iter().flat_map(|s| s.points.iter().map(|&(_, y)| y));
+	let Some(first) = ys.next() else {
+		return (-4.0, 4.0);
+	};
+	let (mut min_y, mut max_y) = ys.fold((first, first), |(min_y, max_y), y| (min_y.min(y), max_y.max(y)));
+	if min_y == max_y {
+		min_y -= 1.0;
+		max_y += 1.0;
+	}
+	let span = (max_y - min_y).max(0.2);
+	let pad = 0.05 * span;
+	let y_min = min_y - pad;
+	let y_max = max_y + pad;
+	(y_min, y_max)
+}
+
+fn view_ticks(view: &PlotView, (x_min, x_max): (f64, f64), (y_min, y_max): (f64, f64)) -> (Ticks, Ticks) {
+	let x_ticks = axis_ticks(x_min, x_max, view.x_tick_step, view.x_tick_labels);
+	let y_ticks = axis_ticks(y_min, y_max, view.y_tick_step, view.y_tick_labels);
+	(x_ticks, y_ticks)
+}
+
+fn marker_radius(px: u32) -> i32 {
+	if px >= 1000 { 2 } else { 1 }
+}
+
+fn effective_marker_radius(view: &PlotView, (w, h): (u32, u32)) -> i32 {
+	match view.marker_size {
+		Some(n) => scaled(view, n as i32).max(1),
+		None => marker_radius(w.min(h)),
+	}
+}
+
+fn title_font_px(view: &PlotView) -> i32 {
+	scaled(view, view.title_font_px.unwrap_or(64) as i32)
+}
+
+fn axis_label_font_px(view: &PlotView) -> i32 {
+	scaled(view, view.axis_label_font_px.unwrap_or(36) as i32)
+}
+
+fn tick_font_px(view: &PlotView) -> i32 {
+	scaled(view, view.tick_font_px.unwrap_or(28) as i32)
+}
+
+fn html_font(size: i32) -> usize {
+	if size <= 0 { 1 } else { size as usize }
+}
+
+fn left_label_area(view: &PlotView) -> u32 {
+	let tick = tick_font_px(view).max(1);
+	let axis = axis_label_font_px(view).max(1);
+	let base = tick * 2 + axis;
+	base.max(110) as u32
+}
+
+fn bottom_label_area(view: &PlotView) -> u32 {
+	let tick = tick_font_px(view).max(1);
+	let axis = axis_label_font_px(view).max(1);
+	let base = tick * 2 + axis;
+	base.max(90) as u32
+}
+
+fn top_margin(view: &PlotView) -> usize {
+	let title = title_font_px(view).max(1);
+	((title * 2).max(60)) as usize
+}
+
+fn right_margin(view: &PlotView) -> usize {
+	let tick = tick_font_px(view).max(1);
+	((tick + scaled(view, 30)).max(60) + colourbar_area(view)) as usize
+}
+
+fn static_margin_right(view: &PlotView) -> i32 {
+	let tick = tick_font_px(view).max(1);
+	(tick + scaled(view, 40)).max(60) + colourbar_area(view)
+}
+
+fn html_plot_area(view: &PlotView, w: u32, h: u32) -> (usize, usize) {
+	let width = (w as usize).saturating_sub(left_label_area(view) as usize + right_margin(view));
+	let height = (h as usize).saturating_sub(top_margin(view) + bottom_label_area(view) as usize);
+	(width.max(1), height.max(1))
+}
+
+// Room for the colourbar and its labels to the right of a density or coloured points plot; the
+// latter also carries a title naming the quantity.
+fn colourbar_area(view: &PlotView) -> i32 {
+	let bar = scaled(view, COLOURBAR_GAP + COLOURBAR_WIDTH) + tick_font_px(view).max(1) * 4;
+	match (view.render, view.colour_by) {
+		(RenderMode::Points, ColourBy::None) => 0,
+		(RenderMode::Points, _) => bar + colourbar_title_area(view),
+		(RenderMode::Density, _) => bar,
+	}
+}
+
+fn colourbar_title_area(view: &PlotView) -> i32 {
+	axis_label_font_px(view).max(1) * 3 / 2
+}
+
+// Marker colours: one for every point, or one per point in the order given.
+#[derive(Clone, Copy)]
+enum Fill<'a> {
+	Solid([u8; 3]),
+	Each(&'a [[u8; 3]]),
+}
+
+impl Fill<'_> {
+	fn at(self, index: usize) -> [u8; 3] {
+		match self {
+			Fill::Solid(colour) => colour,
+			Fill::Each(colours) => colours[index],
+		}
+	}
+}
+
+#[derive(Clone, Copy)]
+struct MarkerStyle<'a> {
+	shape: MarkerShape,
+	radius: i32,
+	fill: Fill<'a>,
+	// Opacity as 0–255, scaling each marker's coverage.
+	alpha: u8,
+}
+
+// Circumradius of a marker of the given radius; pointed shapes are enlarged to look as heavy as discs.
+fn shape_size(shape: MarkerShape, radius: f64) -> f64 {
+	match shape {
+		MarkerShape::Circle | MarkerShape::Square => radius,
+		MarkerShape::Diamond | MarkerShape::Triangle => radius * 1.3,
+	}
+}
+
+// Corners of a polygonal marker relative to its centre, y pointing down; None for circles.
+fn shape_outline(shape: MarkerShape, radius: f64) -> Option<Vec<(f64, f64)>> {
+	let s = shape_size(shape, radius);
+	match shape {
+		MarkerShape::Circle => None,
+		MarkerShape::Square => Some(vec![(-s, -s), (s, -s), (s, s), (-s, s)]),
+		MarkerShape::Diamond => Some(vec![(0.0, -s), (s, 0.0), (0.0, s), (-s, 0.0)]),
+		MarkerShape::Triangle => {
+			let half = s * 0.75f64.sqrt();
+			Some(vec![(0.0, -s), (half, s / 2.0), (-half, s / 2.0)])
+		}
+	}
+}
+
+fn pixel_outline(shape: MarkerShape, radius: i32) -> Vec<(i32, i32)> {
+	shape_outline(shape, radius as f64)
+		.unwrap_or_default()
+		.into_iter()
+		.map(|(dx, dy)| (dx.round() as i32, dy.round() as i32))
+		.collect()
+}
+
+// The colour and marker of series `index` of `count`: a lone series defaults to `plot.marker_colour`,
+// else the foreground, and several take the palette in turn.
+fn series_style(view: &PlotView, index: usize, count: usize) -> ([u8; 3], MarkerShape) {
+	let style = view.series.get(index);
+	let lone = || view.marker_colour.as_deref().and_then(parse_colour).unwrap_or(foreground(view));
+	let colour = style
+		.and_then(|s| s.colour.as_deref())
+		.and_then(parse_colour)
+		.unwrap_or_else(|| if count == 1 { lone() } else { hex_rgb(SERIES_PALETTE[index % SERIES_PALETTE.len()]) });
+	(colour, style.map_or(MarkerShape::Circle, |s| s.marker))
+}
+
+fn series_label<'a>(view: &'a PlotView, series: &'a [Series<'_>], index: usize) -> &'a str {
+	view.series.get(index).and_then(|s| s.label.as_deref()).unwrap_or(&series[index].label)
+}
+
+fn show_legend(view: &PlotView, series: &[Series<'_>]) -> bool {
+	series.len() > 1 && view.legend != LegendPosition::None
+}
+
+// A plotly colour given as a number on the trace's colour scale.
+#[derive(Clone, Debug, Serialize)]
+#[serde(transparent)]
+struct ScaleValue(f64);
+
+impl PlotlyColor for ScaleValue {}
+
+// How the static chart draws markers: one plotters element each, a raster of the given canvas rows,
+// or SVG path elements left in the string for the caller to put in place of a placeholder.
+enum PointLayer<'a> {
+	Circles,
+	Raster(Range<i32>),
+	Path(&'a mut String),
+}
+
+// A series as the static chart draws it.
+struct Drawn<'a> {
+	label: &'a str,
+	points: &'a [(f64, f64)],
+	style: MarkerStyle<'a>,
+	legend_colour: [u8; 3],
+}
+
+// Everything the static chart needs apart from the backend and the point layer.
+struct Chart<'a> {
+	view: &'a PlotView,
+	series: Vec<Drawn<'a>>,
+	// Every series' points together, for density plots.
+	all_points: &'a [(f64, f64)],
+	colouring: Option<&'a Colouring>,
+	legend: bool,
+	x_range: (f64, f64),
+	y_range: (f64, f64),
+	x_ticks: Ticks,
+	y_ticks: Ticks,
+	// Worked out by the first PNG strip and reused by the rest, which all share one plot area.
+	projected: OnceCell<Vec<Projected>>,
+	density_max: OnceCell<u32>,
+}
+
+impl Chart<'_> {
+	fn project(&self, size: (usize, usize)) -> Vec<Projected> {
+		self.series.iter().map(|s| project(s.points, s.style.fill, self.x_range, self.y_range, size)).collect()
+	}
+}
+
+fn draw_static_chart<B: DrawingBackend>(
+	area: DrawingArea<B, Shift>,
+	spec: &Chart<'_>,
+	layer: PointLayer<'_>,
+) -> std::result::Result<(), DrawingAreaErrorKind<B::ErrorType>> {
+	let view = spec.view;
+	let ((x_min, x_max), (y_min, y_max)) = (spec.x_range, spec.y_range);
+	let (rx, ry) = (&spec.x_ticks, &spec.y_ticks);
+	let layer_rows = match &layer {
+		PointLayer::Raster(rows) => Some(rows.clone()),
+		PointLayer::Circles | PointLayer::Path(_) => None,
+	};
+	let (fg, font) = (colour(foreground(view)), font_family(view));
+	area.fill(&colour(background(view)))?;
+	let mut builder = ChartBuilder::on(&area);
+	builder
+		.margin(scaled(view, STATIC_MARGIN))
+		.margin_right(static_margin_right(view))
+		.caption(view.title.clone(), (font, title_font_px(view)).into_font().color(&fg));
+	builder
+		.set_label_area_size(LabelAreaPosition::Left, left_label_area(view))
+		.set_label_area_size(LabelAreaPosition::Bottom, bottom_label_area(view));
+	let mut chart = builder.build_cartesian_2d(
+		rx.axis(x_min, x_max),
+		ry.axis(y_min, y_max),
+	)?;
+	chart
+		.configure_mesh()
+		.disable_mesh()
+		.x_desc(x_label(view))
+		.y_desc(y_label(view))
+		.axis_style(fg)
+		.axis_desc_style((font, axis_label_font_px(view)).into_font().color(&fg))
+		.label_style((font, tick_font_px(view)).into_font().color(&fg))
+		.x_labels(rx.values.len())
+		.x_label_formatter(&|v| rx.label_for(*v))
+		.y_labels(ry.values.len())
+		.y_label_formatter(&|v| ry.label_for(*v))
+		.draw()?;
+	let (columns, rows) = chart.plotting_area().get_pixel_range();
+	match (view.render, layer) {
+		(RenderMode::Points, PointLayer::Raster(_)) => {
+			let plot_area = chart.plotting_area().strip_coord_spec();
+			if let Some((size, rows)) = raster_window(&plot_area, layer_rows).filter(|(_, rows)| !rows.is_empty()) {
+				let whole;
+				let projected = if rows.len() == size.1 {
+					whole = spec.project(size);
+					&whole
+				} else {
+					spec.projected.get_or_init(|| spec.project(size))
+				};
+				let mut raster = background(view).repeat(size.0 * rows.len());
+				for (series, projected) in spec.series.iter().zip(projected) {
+					splat(&mut raster, projected, series.style, size.0, rows.clone(), view.antialias);
+				}
+				blit_rows(&plot_area, size.0, rows, raster)?;
+			}
+		}
+		(RenderMode::Points, PointLayer::Path(path)) => {
+			for series in &spec.series {
+				path.push_str(&marker_path(series.points, series.style, (x_min, x_max), (y_min, y_max), (columns.clone(), rows.clone())));
+			}
+			area.draw(&Text::new(MARKER_PLACEHOLDER, (columns.start, rows.start), (font, 1)))?;
+		}
+		(RenderMode::Points, PointLayer::Circles) => {
+			for series in &spec.series {
+				let MarkerStyle { shape, radius, fill, alpha } = series.style;
+				let colour = |i: usize| match alpha {
+					255 => colour(fill.at(i)).filled(),
+					_ => colour(fill.at(i)).mix(alpha as f64 / 255.0).filled(),
+				};
+				// Plotters pins out-of-range points to the frame, so drop them instead.
+				let visible = series
+					.points
+					.iter()
+					.enumerate()
+					.filter(|(_, (x, y))| (x_min..=x_max).contains(x) && (y_min..=y_max).contains(y));
+				if shape == MarkerShape::Circle {
+					chart.draw_series(visible.map(|(i, (x, y))| Circle::new((*x, *y), radius, colour(i))))?;
+				} else {
+					let outline = pixel_outline(shape, radius);
+					chart.draw_series(
+						visible.map(|(i, (x, y))| EmptyElement::at((*x, *y)) + Polygon::new(outline.clone(), colour(i))),
+					)?;
+				}
+			}
+		}
+		(RenderMode::Density, _) => {
+			let plot_area = chart.plotting_area().strip_coord_spec();
+			if let Some((size, window)) = raster_window(&plot_area, layer_rows) {
+				let max = (window.len() < size.1)
+					.then(|| *spec.density_max.get_or_init(|| Density::fullest(spec.all_points, (x_min, x_max), (y_min, y_max), size)));
+				let density = Density::bin(spec.all_points, (x_min, x_max), (y_min, y_max), size, window.clone(), max);
+				if !window.is_empty() {
+					blit_rows(&plot_area, size.0, window, density.raster(view.colormap, view.density_scale, background(view)))?;
+				}
+				let ticks = density.colourbar_ticks(view.density_scale);
+				draw_colourbar(&area, view, &ticks, None, columns.end + scaled(view, COLOURBAR_GAP), rows.clone())?;
+			}
+		}
+	}
+	if let (RenderMode::Points, Some(colouring)) = (view.render, spec.colouring) {
+		draw_colourbar(&area, view, &colouring.level_ticks(), Some(colouring.title), columns.end + scaled(view, COLOURBAR_GAP), rows.clone())?;
+	}
+	let frame_style = ShapeStyle::from(&fg).stroke_width(frame_width(view));
+	chart
+		.plotting_area()
+		.draw(&Rectangle::new([(x_min, y_min), (x_max, y_max)], frame_style))?;
+	if spec.legend && view.render == RenderMode::Points {
+		draw_legend(&area, view, &spec.series, (columns, rows))?;
+	}
+	area.present()
+}
+
+// Each series' marker and label in a box inset from one corner of the plot area.
+fn draw_legend<B: DrawingBackend>(
+	area: &DrawingArea<B, Shift>,
+	view: &PlotView,
+	series: &[Drawn<'_>],
+	(columns, rows): (Range<i32>, Range<i32>),
+) -> std::result::Result<(), DrawingAreaErrorKind<B::ErrorType>> {
+	let tick = tick_font_px(view).max(1);
+	let fg = colour(foreground(view));
+	let label_style = TextStyle::from((font_family(view), tick).into_font()).color(&fg).pos(Pos::new(HPos::Left, VPos::Center));
+	let (pad, line, inset) = (tick / 2, tick * 3 / 2, scaled(view, 10));
+	let mut text_width = 0;
+	for s in series {
+		text_width = text_width.max(area.estimate_text_size(s.label, &label_style)?.0 as i32);
+	}
+	let (width, height) = (pad * 3 + tick + text_width, pad * 2 + line * series.len() as i32);
+	let left = match view.legend {
+		LegendPosition::UpperLeft | LegendPosition::LowerLeft => columns.start + inset,
+		_ => columns.end - inset - width,
+	};
+	let top = match view.legend {
+		LegendPosition::LowerLeft | LegendPosition::LowerRight => rows.end - inset - height,
+		_ => rows.start + inset,
+	};
+	let corners = vec![(left, top), (left + width, top), (left + width, top + height), (left, top + height)];
+	area.draw(&Polygon::new(corners.clone(), colour(background(view)).filled()))?;
+	// An outlined rectangle would lose its stroke width in SVG, so the border is a closed path.
+	area.draw(&PathElement::new([corners.clone(), vec![corners[0]]].concat(), fg.stroke_width(line_width(view, 1))))?;
+	for (k, s) in series.iter().enumerate() {
+		let (x, y) = (left + pad + tick / 2, top + pad + line * k as i32 + line / 2);
+		let radius = s.style.radius.max(tick / 4);
+		let style = colour(s.legend_colour).filled();
+		match s.style.shape {
+			MarkerShape::Circle => area.draw(&Circle::new((x, y), radius, style))?,
+			shape => area.draw(&Polygon::new(pixel_outline(shape, radius).into_iter().map(|(dx, dy)| (x + dx, y + dy)).collect::<Vec<_>>(), style))?,
+		}
+		area.draw(&Text::new(s.label, (left + pad * 2 + tick, y), label_style.clone()))?;
+	}
+	Ok(())
+}
+
+// The plot-area size and the rows of it covered by `layer_rows` (every row when drawing vectors);
+// the window is empty when a PNG strip misses the plot area.
+fn raster_window<B: DrawingBackend>(
+	plot_area: &DrawingArea<B, Shift>,
+	layer_rows: Option<Range<i32>>,
+) -> Option<((usize, usize), Range<usize>)> {
+	let (columns, rows) = plot_area.get_pixel_range();
+	let (width, height) = (columns.len(), rows.len());
+	let layer_rows = layer_rows.unwrap_or(rows.clone());
+	let first = (layer_rows.start - rows.start).clamp(0, height as i32) as usize;
+	let last = (layer_rows.end - rows.start).clamp(0, height as i32) as usize;
+	(width > 0 && height > 0).then_some(((width, height), first..last))
+}
+
+fn blit_rows<B: DrawingBackend>(
+	plot_area: &DrawingArea<B, Shift>,
+	width: usize,
+	rows: Range<usize>,
+	raster: Vec<u8>,
+) -> std::result::Result<(), DrawingAreaErrorKind<B::ErrorType>> {
+	let image = BitMapElement::with_owned_buffer((0, rows.start as i32), (width as u32, rows.len() as u32), raster)
+		.expect("raster matches the plot area");
+	plot_area.draw(&image)
+}
+
+// `ticks` are (level, label) pairs with levels in [0, 1] from the bottom of the bar.
+fn draw_colourbar<B: DrawingBackend>(
+	area: &DrawingArea<B, Shift>,
+	view: &PlotView,
+	ticks: &[(f64, String)],
+	title: Option<&str>,
+	left: i32,
+	rows: Range<i32>,
+) -> std::result::Result<(), DrawingAreaErrorKind<B::ErrorType>> {
+	let (top, bottom) = (rows.start, rows.end);
+	let right = left + scaled(view, COLOURBAR_WIDTH);
+	let bands = COLOURBAR_BANDS.min(bottom - top).max(1);
+	for band in 0..bands {
+		let band_top = top + (bottom - top) * band / bands;
+		let band_bottom = top + (bottom - top) * (band + 1) / bands;
+		let [r, g, b] = rgb(view.colormap, 1.0 - (band as f64 + 0.5) / bands as f64);
+		area.draw(&Rectangle::new([(left, band_top), (right, band_bottom)], RGBColor(r, g, b).filled()))?;
+	}
+	let fg = colour(foreground(view));
+	area.draw(&Rectangle::new([(left, top), (right, bottom)], ShapeStyle::from(&fg).stroke_width(frame_width(view))))?;
+	let tick = tick_font_px(view);
+	let label_style = TextStyle::from((font_family(view), tick).into_font()).color(&fg).pos(Pos::new(HPos::Left, VPos::Center));
+	for (level, label) in ticks {
+		let y = bottom - ((bottom - top) as f64 * level).round() as i32;
+		area.draw(&PathElement::new(vec![(right, y), (right + scaled(view, 6), y)], fg.stroke_width(frame_width(view))))?;
+		area.draw(&Text::new(label.as_str(), (right + scaled(view, 10), y), label_style.clone()))?;
+	}
+	if let Some(title) = title {
+		let x = right + tick.max(1) * 4 + colourbar_title_area(view) / 2;
+		let title_style = TextStyle::from((font_family(view), axis_label_font_px(view)).into_font())
+			.color(&fg)
+			.transform(FontTransform::Rotate270)
+			.pos(Pos::new(HPos::Center, VPos::Center));
+		area.draw(&Text::new(title, (x, (top + bottom) / 2), title_style))?;
+	}
+	Ok(())
+}
+
+
+fn save_static_with_xy(
+	series: &[Series<'_>],
+	colouring: Option<&Colouring>,
+	view: &PlotView,
+	out_png: &str,
+	out_svg: &str,
+	x_range: (f64, f64),
+	y_range: (f64, f64),
+) -> Result<()> {
+	let (w, h) = static_canvas(view, x_range, y_range);
+	let (x_ticks, y_ticks) = view_ticks(view, x_range, y_range);
+	let radius = effective_marker_radius(view, (w, h));
+	let colours: Vec<Option<Vec<[u8; 3]>>> = series
+		.iter()
+		.map(|s| colouring.zip(s.values).map(|(colouring, values)| colouring.colours(view.colormap, values)))
+		.collect();
+	let drawn = series
+		.iter()
+		.zip(&colours)
+		.enumerate()
+		.map(|(index, (s, colours))| {
+			let (colour, shape) = series_style(view, index, series.len());
+			let fill = colours.as_deref().map_or(Fill::Solid(colour), Fill::Each);
+			// Per-point colours stand for values, so the legend keys series by marker alone.
+			let legend_colour = if colouring.is_some() { foreground(view) } else { colour };
+			let style = MarkerStyle { shape, radius, fill, alpha: marker_alpha(view) };
+			Drawn { label: series_label(view, series, index), points: s.points, style, legend_colour }
+		})
+		.collect();
+	let combined: Vec<(f64, f64)>;
+	let all_points = match series {
+		[only] => only.points,
+		_ if view.render == RenderMode::Density => {
+			combined = series.iter().flat_map(|s| s.points.iter().copied()).collect();
+			&combined
+		}
+		_ => &[],
+	};
+	let chart = Chart {
+		view,
+		series: drawn,
+		all_points,
+		colouring,
+		legend: show_legend(view, series),
+		x_range,
+		y_range,
+		x_ticks,
+		y_ticks,
+		projected: OnceCell::new(),
+		density_max: OnceCell::new(),
+	};
+	write_png_strips(Path::new(out_png), (w, h), png_strip_rows(view), view.dpi, |strip, rows| {
+		draw_static_chart(strip.into_drawing_area(), &chart, PointLayer::Raster(rows))
+	})?;
+	let draw_svg =
+		|backend: SVGBackend, layer| draw_static_chart(backend.into_drawing_area(), &chart, layer).map_err(|e| Error::plot(out_svg, e));
+	match view.svg_points {
+		SvgPoints::Raster => draw_svg(SVGBackend::new(out_svg, (w, h)), PointLayer::Raster(0..h as i32)),
+		SvgPoints::Path if view.render == RenderMode::Points => {
+			let (mut svg, mut path) = (String::new(), String::new());
+			draw_svg(SVGBackend::with_string(&mut svg, (w, h)), PointLayer::Path(&mut path))?;
+			replace_placeholder(&mut svg, &path);
+			std::fs::write(out_svg, svg).map_err(|e| Error::io(out_svg, e))
+		}
+		// A density plot has no markers to gather into paths.
+		SvgPoints::Circles | SvgPoints::Path => draw_svg(SVGBackend::new(out_svg, (w, h)), PointLayer::Circles),
+	}
+}
+
+
+fn save_html_with_xy(
+	series: &[Series<'_>],
+	colouring: Option<&Colouring>,
+	view: &PlotView,
+	out_html: &str,
+	(x_min, x_max): (f64, f64),
+	(y_min, y_max): (f64, f64),
+) -> Result<()> {
+	let (w, h) = html_canvas(view, (x_min, x_max), (y_min, y_max));
+	let (x_ticks, y_ticks) = view_ticks(view, (x_min, x_max), (y_min, y_max));
+	let tick_font = html_font(tick_font_px(view));
+	let axis_label_font = html_font(axis_label_font_px(view));
+	let title_font = html_font(title_font_px(view));
+	let fg = css(foreground(view));
+	let x_axis = Axis::new()
+		.range(vec![x_min, x_max])
+		.show_grid(false)
+		.zero_line(false)
+		.show_line(true)
+		.line_color(fg.clone())
+		.line_width(frame_width(view) as usize)
+		.tick_color(fg.clone())
+		.tick_values(x_ticks.values)
+		.tick_text(x_ticks.labels)
+		.tick_font(Font::new().size(tick_font))
+		.title(Title::new(x_label(view)).font(Font::new().size(axis_label_font)));
+	let y_axis = {
+		let axis = Axis::new()
+			.range(vec![y_min, y_max])
+			.show_grid(false)
+			.zero_line(false)
+			.show_line(true)
+			.line_color(fg.clone())
+			.line_width(frame_width(view) as usize)
+			.tick_color(fg.clone())
+			.tick_font(Font::new().size(tick_font))
+			.title(Title::new(y_label(view)).font(Font::new().size(axis_label_font)));
+		if y_ticks.values.is_empty() {
+			axis
+		} else {
+			axis.tick_values(y_ticks.values).tick_text(y_ticks.labels)
+		}
+	};
+	// Text takes its family and colour from the layout font unless a trace overrides them.
+	let layout = Layout::new()
+		.font(Font::new().family(font_family(view)).color(fg.clone()))
+		.paper_background_color(css(background(view)))
+		.plot_background_color(css(background(view)))
+		.title(Title::new(&view.title).font(Font::new().size(title_font)))
+		.width(w as usize)
+		.height(h as usize)
+		.margin(
+			Margin::new()
+				.left(left_label_area(view) as usize)
+				.right(right_margin(view))
+				.top(top_margin(view))
+				.bottom(bottom_label_area(view) as usize),
+		)
+		.x_axis(x_axis)
+		.y_axis(y_axis)
+		.show_legend(show_legend(view, series));
+	let layout = match view.legend {
+		LegendPosition::UpperRight | LegendPosition::None => layout.legend(html_legend(view, 0.99, Anchor::Right, 0.99, Anchor::Top)),
+		LegendPosition::UpperLeft => layout.legend(html_legend(view, 0.01, Anchor::Left, 0.99, Anchor::Top)),
+		LegendPosition::LowerLeft => layout.legend(html_legend(view, 0.01, Anchor::Left, 0.01, Anchor::Bottom)),
+		LegendPosition::LowerRight => layout.legend(html_legend(view, 0.99, Anchor::Right, 0.01, Anchor::Bottom)),
+	};
+	let mut plot = Plot::new();
+	match view.render {
+		RenderMode::Points => {
+			for (index, s) in series.iter().enumerate() {
+				let points = s.points;
+				let kept: Vec<usize> = match view.html_point_budget {
+					Some(budget) => decimate(points, (x_min, x_max), (y_min, y_max), html_plot_area(view, w, h), budget as usize),
+					None => (0..points.len()).collect(),
+				};
+				let xs: Vec<f64> = kept.iter().map(|&i| points[i].0).collect();
+				let ys: Vec<f64> = kept.iter().map(|&i| points[i].1).collect();
+				let (colour, shape) = series_style(view, index, series.len());
+				let symbol = match shape {
+					MarkerShape::Circle => MarkerSymbol::Circle,
+					MarkerShape::Square => MarkerSymbol::Square,
+					MarkerShape::Diamond => MarkerSymbol::Diamond,
+					MarkerShape::Triangle => MarkerSymbol::TriangleUp,
+				};
+				let opacity = view.marker_opacity.unwrap_or(0.8);
+				let marker = Marker::new().size(effective_marker_radius(view, (w, h)) as usize).opacity(opacity).symbol(symbol);
+				let marker = match colouring.zip(s.values) {
+					Some((colouring, values)) => {
+						let (ticks, labels): (Vec<f64>, Vec<String>) = colouring.ticks.iter().cloned().unzip();
+						let color_bar = ColorBar::new()
+							.title(Title::new(colouring.title).font(Font::new().size(axis_label_font)))
+							.tick_vals(ticks)
+							.tick_text(labels)
+							.tick_font(Font::new().size(tick_font));
+						// Every trace shares the scale; the first one shows it.
+						marker
+							.color_array(kept.iter().map(|&i| ScaleValue(values[i])).collect())
+							.cmin(colouring.min)
+							.cmax(colouring.max)
+							.color_scale(ColorScale::Vector(plotly_scale(view.colormap)))
+							.show_scale(index == 0)
+							.color_bar(color_bar)
+					}
+					None => marker.color(css(colour)),
+				};
+				let trace = Scatter::new(xs, ys).mode(Mode::Markers).marker(marker).name(series_label(view, series, index));
+				plot.add_trace(trace.web_gl_mode(kept.len() > WEBGL_MIN_POINTS));
+			}
+		}
+		RenderMode::Density => {
+			let (plot_width, plot_height) = html_plot_area(view, w, h);
+			let (columns, rows) = (plot_width.clamp(1, MAX_HTML_BINS), plot_height.clamp(1, MAX_HTML_BINS));
+			let all_points: Vec<(f64, f64)> = series.iter().flat_map(|s| s.points.iter().copied()).collect();
+			let density = Density::bin(&all_points, (x_min, x_max), (y_min, y_max), (columns, rows), 0..rows, None);
+			let centres = |min: f64, max: f64, n: usize| -> Vec<f64> {
+				(0..n).map(|i| min + (max - min) * (i as f64 + 0.5) / n as f64).collect()
+			};
+			let (levels, labels): (Vec<f64>, Vec<String>) =
+				density.colourbar_ticks(view.density_scale).into_iter().unzip();
+			let color_bar = ColorBar::new().tick_vals(levels).tick_text(labels).tick_font(Font::new().size(tick_font));
+			let trace = HeatMap::new(
+				centres(x_min, x_max, columns),
+				centres(y_min, y_max, rows),
+				density.rows_bottom_up(view.density_scale),
+			)
+			.color_scale(ColorScale::Vector(plotly_scale(view.colormap)))
+			.color_bar(color_bar);
+			plot.add_trace(trace);
+		}
+	}
+	plot.set_layout(layout);
+	std::fs::write(out_html, plot.to_html()).map_err(|e| Error::io(out_html, e))
+}
+
+fn html_legend(view: &PlotView, x: f64, x_anchor: Anchor, y: f64, y_anchor: Anchor) -> Legend {
+	Legend::new()
+		.x(x)
+		.x_anchor(x_anchor)
+		.y(y)
+		.y_anchor(y_anchor)
+		.background_color(css(background(view)))
+		.border_color(css(foreground(view)))
+		.border_width(line_width(view, 1) as usize)
+		.font(Font::new().size(html_font(tick_font_px(view))))
+}
+
+// A set of points to draw: `label` names it in the legend when several series share a figure, and
+// `values` holds one number per point for `plot.colour_by`, as from `point_values`.
+pub struct Series<'a> {
+	pub label: String,
+	pub points: &'a [(f64, f64)],
+	pub values: Option<&'a [f64]>,
+}
+
+pub fn save_all(series: &[Series<'_>], view: &PlotView, out_base: &str) -> Result<Vec<PathBuf>> {
+	let [x_min, x_max] = view.x_range.unwrap_or([-4.0, 4.0]);
+	save_all_x(series, view, out_base, x_min, x_max)
+}
+
+pub fn save_all_x(series: &[Series<'_>], view: &PlotView, out_base: &str, x_min: f64, x_max: f64) -> Result<Vec<PathBuf>> {
+	let y_range = match view.y_range {
+		Some([y_min, y_max]) => (y_min, y_max),
+		None => data_y_range(series),
+	};
+	save_all_xy(series, view, out_base, (x_min, x_max), y_range)
+}
+
+pub fn save_all_xy(
+	series: &[Series<'_>],
+	view: &PlotView,
+	out_base: &str,
+	x_range: (f64, f64),
+	y_range: (f64, f64),
+) -> Result<Vec<PathBuf>> {
+	let png = output_file(out_base, "png")?;
+	let svg = output_file(out_base, "svg")?;
+	let html = output_file(out_base, "html")?;
+	let out_png = png.to_string_lossy().into_owned();
+	let out_svg = svg.to_string_lossy().into_owned();
+	let out_html = html.to_string_lossy().into_owned();
+	let colouring = series_colouring(series, view);
+	save_static_with_xy(series, colouring.as_ref(), view, &out_png, &out_svg, x_range, y_range)?;
+	save_html_with_xy(series, colouring.as_ref(), view, &out_html, x_range, y_range)?;
+	Ok(vec![png, svg, html])
+}
+
+// One colour scale across every series, so that equal values look alike wherever they are drawn.
+fn series_colouring(series: &[Series<'_>], view: &PlotView) -> Option<Colouring> {
+	if view.render != RenderMode::Points || series.iter().all(|s| s.values.is_none()) {
+		return None;
+	}
+	let values: Vec<f64> = series.iter().flat_map(|s| s.values.unwrap_or_default().iter().copied()).collect();
+	Colouring::new(view.colour_by, &values)
+}
+
+pub fn save_region(series: &[Series<'_>], view: &PlotView, out_base: &str, region: &Region) -> Result<Vec<PathBuf>> {
+	let [theta_min, theta_max] = region.theta;
+	let in_region: Vec<_> = series
+		.iter()
+		.map(|s| {
+			let kept: Vec<usize> = (0..s.points.len())
+				.filter(|&i| {
+					let (theta, omega) = s.points[i];
+					theta >= theta_min
+						&& theta <= theta_max
+						&& region.omega.is_none_or(|[omega_min, omega_max]| omega >= omega_min && omega <= omega_max)
+				})
+				.collect();
+			let points: Vec<(f64, f64)> = kept.iter().map(|&i| s.points[i]).collect();
+			let values: Option<Vec<f64>> = s.values.map(|values| kept.iter().map(|&i| values[i]).collect());
+			(points, values)
+		})
+		.collect();
+	let region_series: Vec<Series<'_>> = series
+		.iter()
+		.zip(&in_region)
+		.map(|(s, (points, values))| Series { label: s.label.clone(), points, values: values.as_deref() })
+		.collect();
+	let mut region_view = view.clone();
+	if let Some(title) = &region.title {
+		region_view.title = title.clone();
+	}
+	if let Some(side_px) = region.side_px {
+		region_view.side_px = side_px;
+		region_view.width_px = None;
+		region_view.height_px = None;
+	}
+	let y_range = match region.omega {
+		Some([omega_min, omega_max]) => (omega_min, omega_max),
+		None => data_y_range(&region_series),
+	};
+	let region_base = format!("{out_base}__{}", region.name);
+	save_all_xy(&region_series, &region_view, &region_base, (theta_min, theta_max), y_range)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_support::{outcome, spec};
+
+	fn png_bytes(series: &[Series<'_>], view: &PlotView, path: &Path) -> Vec<u8> {
+		let colouring = series_colouring(series, view);
+		let (png, svg) = (path.with_extension("png"), path.with_extension("svg"));
+		let (png, svg) = (png.to_str().unwrap(), svg.to_str().unwrap());
+		save_static_with_xy(series, colouring.as_ref(), view, png, svg, (-4.0, 4.0), data_y_range(series)).unwrap();
+		std::fs::read(png).unwrap()
+	}
+
+	#[test]
+	fn strips_match_a_single_strip() {
+		let dir = std::env::temp_dir().join(format!("pendulum-poincare-strips-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		let samples = outcome(5000).samples;
+		let points: Vec<(f64, f64)> = samples.iter().map(|s| (s.theta, s.omega)).collect();
+		let periods: Vec<f64> = samples.iter().map(|s| s.period as f64).collect();
+		let mut view = spec().plot;
+		view.antialias = true;
+		let solid = [Series { label: "t".to_string(), points: &points, values: None }];
+		let coloured = [Series { label: "t".to_string(), points: &points, values: Some(&periods) }];
+		let cases = [(RenderMode::Points, ColourBy::None, &solid), (RenderMode::Points, ColourBy::Period, &coloured), (RenderMode::Density, ColourBy::None, &solid)];
+		for (render, colour_by, series) in cases {
+			view.render = render;
+			view.colour_by = colour_by;
+			view.strip_rows = Some(u32::MAX);
+			let whole = png_bytes(series, &view, &dir.join("whole"));
+			view.strip_rows = Some(37);
+			let strips = png_bytes(series, &view, &dir.join("strips"));
+			assert!(whole == strips, "{render:?} {colour_by:?} strips differ from a single strip");
+		}
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn density_svg_ignores_path_points() {
+		let dir = std::env::temp_dir().join(format!("pendulum-poincare-density-svg-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		let points: Vec<(f64, f64)> = outcome(500).samples.iter().map(|s| (s.theta, s.omega)).collect();
+		let series = [Series { label: "t".to_string(), points: &points, values: None }];
+		let mut view = spec().plot;
+		view.render = RenderMode::Density;
+		let mut svgs = Vec::new();
+		for svg_points in [SvgPoints::Path, SvgPoints::Circles] {
+			view.svg_points = svg_points;
+			png_bytes(&series, &view, &dir.join("density"));
+			svgs.push(std::fs::read_to_string(dir.join("density.svg")).unwrap());
+		}
+		assert!(svgs[0] == svgs[1] && svgs[0].contains("<image") && !svgs[0].contains(MARKER_PLACEHOLDER));
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn equal_aspect_canvas_is_capped() {
+		let mut view = spec().plot;
+		view.aspect = Aspect::Equal;
+		let (w, h) = static_canvas(&view, (-4.0, 4.0), (-2.0, 2.0));
+		assert!(w == 800 && h > MIN_SIDE_PX && h < w);
+		assert_eq!(static_canvas(&view, (0.0, 1e-9), (-4.0, 4.0)), (800, max_canvas_height(&view)));
+		assert!(html_canvas(&view, (-4.0, 4.0), (-1e-9, 1e-9)).1 >= MIN_SIDE_PX);
+	}
+}
@@ -0,0 +1,176 @@
+use super::{shape_size, Fill, MarkerStyle};
+use crate::types::MarkerShape;
+use rayon::prelude::*;
+use std::ops::Range;
+
+// The visible points of one series as marker centres in pixels of a `width`×`height` plot area,
+// row 0 at the top, with their colours when each point has its own.
+pub(crate) struct Projected {
+	centres: Vec<(f64, f64)>,
+	colours: Vec<[u8; 3]>,
+}
+
+pub(crate) fn project(
+	points: &[(f64, f64)],
+	fill: Fill<'_>,
+	(x_min, x_max): (f64, f64),
+	(y_min, y_max): (f64, f64),
+	(width, height): (usize, usize),
+) -> Projected {
+	let (sx, sy) = (width as f64 / (x_max - x_min), height as f64 / (y_max - y_min));
+	let visible: Vec<usize> = (0..points.len())
+		.into_par_iter()
+		.filter(|&i| (x_min..=x_max).contains(&points[i].0) && (y_min..=y_max).contains(&points[i].1))
+		.collect();
+	let centres = visible.par_iter().map(|&i| ((points[i].0 - x_min) * sx, (y_max - points[i].1) * sy)).collect();
+	let colours = match fill {
+		Fill::Solid(_) => Vec::new(),
+		Fill::Each(all) => visible.iter().map(|&i| all[i]).collect(),
+	};
+	Projected { centres, colours }
+}
+
+// Paints one series' markers straight into an RGB buffer holding `rows` of the plot area, over
+// whatever earlier series left there. Circles reach half a pixel past the radius, matching the
+// footprint of plotters' filled circles. Rows are split into bands painted in parallel.
+pub(crate) fn splat(pixels: &mut [u8], projected: &Projected, style: MarkerStyle<'_>, width: usize, rows: Range<usize>, antialias: bool) {
+	let fill = match style.fill {
+		Fill::Solid(colour) => Fill::Solid(colour),
+		Fill::Each(_) => Fill::Each(&projected.colours),
+	};
+	splat_centres(pixels, &projected.centres, MarkerStyle { fill, ..style }, width, rows, antialias);
+}
+
+// Like `splat`, for marker centres already given in pixels of a `width`-wide area. A solid fill keeps
+// the strongest coverage of each pixel before compositing, so one series never darkens itself;
+// per-point colours are painted in order.
+pub(crate) fn splat_centres(
+	pixels: &mut [u8],
+	centres: &[(f64, f64)],
+	style: MarkerStyle<'_>,
+	width: usize,
+	rows: Range<usize>,
+	antialias: bool,
+) {
+	match style.fill {
+		Fill::Solid(colour) => {
+			let mut coverage = vec![0u8; width * rows.len()];
+			paint(&mut coverage, 1, centres, style, width, rows, antialias, |cell, _, alpha| cell[0] = cell[0].max(alpha));
+			pixels.par_chunks_mut(3).zip(coverage.par_iter()).for_each(|(pixel, &alpha)| {
+				let alpha = fade(alpha, style.alpha);
+				for (channel, &c) in pixel.iter_mut().zip(&colour) {
+					*channel = over(*channel, c, alpha);
+				}
+			});
+		}
+		Fill::Each(colours) => {
+			paint(pixels, 3, centres, style, width, rows, antialias, |cell, i, alpha| {
+				let alpha = fade(alpha, style.alpha);
+				for (channel, &c) in cell.iter_mut().zip(&colours[i]) {
+					*channel = over(*channel, c, alpha);
+				}
+			});
+		}
+	}
+}
+
+// Coverage scaled by the marker opacity; full opacity leaves it unchanged.
+fn fade(coverage: u8, opacity: u8) -> u8 {
+	((coverage as u32 * opacity as u32 + 127) / 255) as u8
+}
+
+fn over(background: u8, colour: u8, alpha: u8) -> u8 {
+	let (background, colour, alpha) = (background as i32, colour as i32, alpha as i32);
+	(background + ((colour - background) * alpha + (colour - background).signum() * 127) / 255) as u8
+}
+
+// Signed distance in pixels from a marker's edge, negative inside, for a centre offset (dx, dy)
+// with y pointing down.
+fn edge_distance(shape: MarkerShape, radius: f64, dx: f64, dy: f64) -> f64 {
+	let size = shape_size(shape, radius);
+	match shape {
+		MarkerShape::Circle => (dx * dx + dy * dy).sqrt() - size,
+		MarkerShape::Square => dx.abs().max(dy.abs()) - size,
+		MarkerShape::Diamond => (dx.abs() + dy.abs() - size) * std::f64::consts::FRAC_1_SQRT_2,
+		// `size` is the circumradius; the inradius is half of it.
+		MarkerShape::Triangle => (dy - size / 2.0).max(0.75f64.sqrt() * dx.abs() - 0.5 * dy - size / 2.0),
+	}
+}
+
+// Calls `put` with every pixel each marker covers, its index and the coverage; `buffer` holds
+// `channels` bytes per pixel.
+#[allow(clippy::too_many_arguments)]
+fn paint(
+	buffer: &mut [u8],
+	channels: usize,
+	centres: &[(f64, f64)],
+	style: MarkerStyle<'_>,
+	width: usize,
+	rows: Range<usize>,
+	antialias: bool,
+	put: impl Fn(&mut [u8], usize, u8) + Sync,
+) {
+	let radius = style.radius.max(0) as f64 + 0.5;
+	let reach = shape_size(style.shape, radius) + 1.0;
+	let bands = (rayon::current_num_threads() * 4).min(rows.len()).max(1);
+	let band_rows = rows.len().div_ceil(bands).max(1);
+	let stride = width * channels;
+	// Each band visits only the markers reaching into it, in their original order.
+	let empty = || vec![Vec::new(); rows.len().div_ceil(band_rows)];
+	let buckets = centres
+		.par_iter()
+		.enumerate()
+		.fold(empty, |mut buckets, (i, &(_, cy))| {
+			let first = (cy - reach).floor().max(rows.start as f64);
+			let end = (cy + reach).ceil().min(rows.end as f64);
+			if first < end {
+				let (first, last) = (first as usize - rows.start, end as usize - 1 - rows.start);
+				for bucket in &mut buckets[first / band_rows..=last / band_rows] {
+					bucket.push(i);
+				}
+			}
+			buckets
+		})
+		.reduce(empty, |mut left, right| {
+			for (l, r) in left.iter_mut().zip(right) {
+				l.extend(r);
+			}
+			left
+		});
+	buffer.par_chunks_mut(band_rows * stride).zip(buckets).enumerate().for_each(|(band, (cells, bucket))| {
+		let top = rows.start + band * band_rows;
+		let lines = cells.len() / stride;
+		for i in bucket {
+			let (cx, cy) = centres[i];
+			let row_range = ((cy - reach).floor().max(top as f64) as usize)..((cy + reach).ceil().min((top + lines) as f64) as usize);
+			let col_range = ((cx - reach).floor().max(0.0) as usize)..((cx + reach).ceil().min(width as f64) as usize);
+			for row in row_range {
+				let dy = row as f64 + 0.5 - cy;
+				let line = &mut cells[(row - top) * stride..(row - top + 1) * stride];
+				if !antialias && style.shape == MarkerShape::Circle {
+					// Hard-edged discs cover one span per row, no per-pixel distance needed.
+					let half = radius * radius - dy * dy;
+					if half < 0.0 {
+						continue;
+					}
+					let half = half.sqrt();
+					let first = (cx - half - 0.5).ceil().max(0.0) as usize;
+					let last = ((cx + half - 0.5).floor() + 1.0).clamp(0.0, width as f64) as usize;
+					if first < last {
+						line[first * channels..last * channels].chunks_exact_mut(channels).for_each(|cell| put(cell, i, 255));
+					}
+					continue;
+				}
+				for col in col_range.clone() {
+					let distance = edge_distance(style.shape, radius, col as f64 + 0.5 - cx, dy);
+					let alpha = match antialias {
+						true => (0.5 - distance).clamp(0.0, 1.0),
+						false if distance <= 0.0 => 1.0,
+						false => continue,
+					};
+					put(&mut line[col * channels..(col + 1) * channels], i, (alpha * 255.0).round() as u8);
+				}
+			}
+		}
+	});
+}
@@ -0,0 +1,115 @@
+use crate::error::{Error, Result};
+use plotters::prelude::*;
+use plotters_backend::{rasterizer, BackendColor, BackendCoord, BackendStyle, DrawingErrorKind};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::ops::Range;
+use std::path::Path;
+
+type StripError = <BitMapBackend<'static> as DrawingBackend>::ErrorType;
+
+// A horizontal band of a taller canvas. Shapes are rasterised in canvas coordinates and only the
+// resulting pixels, spans and filled rects are shifted into the band, so every band matches the
+// corresponding rows of a single full-size bitmap.
+pub(crate) struct StripBackend<'a> {
+	inner: BitMapBackend<'a>,
+	size: (u32, u32),
+	top: i32,
+}
+
+impl StripBackend<'_> {
+	fn shift(&self, (x, y): BackendCoord) -> BackendCoord {
+		(x, y - self.top)
+	}
+}
+
+impl DrawingBackend for StripBackend<'_> {
+	type ErrorType = StripError;
+
+	fn get_size(&self) -> (u32, u32) {
+		self.size
+	}
+
+	fn ensure_prepared(&mut self) -> std::result::Result<(), DrawingErrorKind<StripError>> {
+		self.inner.ensure_prepared()
+	}
+
+	fn present(&mut self) -> std::result::Result<(), DrawingErrorKind<StripError>> {
+		self.inner.present()
+	}
+
+	fn draw_pixel(&mut self, point: BackendCoord, color: BackendColor) -> std::result::Result<(), DrawingErrorKind<StripError>> {
+		let point = self.shift(point);
+		self.inner.draw_pixel(point, color)
+	}
+
+	fn draw_line<S: BackendStyle>(
+		&mut self,
+		from: BackendCoord,
+		to: BackendCoord,
+		style: &S,
+	) -> std::result::Result<(), DrawingErrorKind<StripError>> {
+		if (from.0 == to.0 || from.1 == to.1) && style.stroke_width() == 1 {
+			let (from, to) = (self.shift(from), self.shift(to));
+			return self.inner.draw_line(from, to, style);
+		}
+		rasterizer::draw_line(self, from, to, style)
+	}
+
+	fn draw_rect<S: BackendStyle>(
+		&mut self,
+		upper_left: BackendCoord,
+		bottom_right: BackendCoord,
+		style: &S,
+		fill: bool,
+	) -> std::result::Result<(), DrawingErrorKind<StripError>> {
+		if fill {
+			let (upper_left, bottom_right) = (self.shift(upper_left), self.shift(bottom_right));
+			return self.inner.draw_rect(upper_left, bottom_right, style, true);
+		}
+		rasterizer::draw_rect(self, upper_left, bottom_right, style, fill)
+	}
+
+	fn blit_bitmap(&mut self, pos: BackendCoord, size: (u32, u32), src: &[u8]) -> std::result::Result<(), DrawingErrorKind<StripError>> {
+		let pos = self.shift(pos);
+		self.inner.blit_bitmap(pos, size, src)
+	}
+}
+
+// Renders the canvas `rows` at a time and streams each band into the PNG encoder, so memory
+// depends on the band height rather than the canvas height.
+pub(crate) fn write_png_strips<E: std::fmt::Display>(
+	path: &Path,
+	(width, height): (u32, u32),
+	rows: u32,
+	dpi: Option<f64>,
+	mut draw: impl FnMut(StripBackend<'_>, Range<i32>) -> std::result::Result<(), E>,
+) -> Result<()> {
+	let file = File::create(path).map_err(|e| Error::io(path, e))?;
+	let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+	encoder.set_color(png::ColorType::Rgb);
+	encoder.set_depth(png::BitDepth::Eight);
+	if let Some(dpi) = dpi {
+		let per_metre = (dpi / 0.0254).round() as u32;
+		encoder.set_pixel_dims(Some(png::PixelDimensions { xppu: per_metre, yppu: per_metre, unit: png::Unit::Meter }));
+	}
+	let encode_error = |e: png::EncodingError| match e {
+		png::EncodingError::IoError(e) => Error::io(path, e),
+		e => Error::plot(path, e),
+	};
+	let mut writer = encoder.write_header().map_err(encode_error)?;
+	let mut stream = writer.stream_writer().map_err(encode_error)?;
+	let rows = rows.clamp(1, height.max(1));
+	let mut buffer = vec![0u8; width as usize * rows as usize * 3];
+	let mut top = 0;
+	while top < height {
+		let band = rows.min(height - top);
+		let pixels = &mut buffer[..width as usize * band as usize * 3];
+		let backend = StripBackend { inner: BitMapBackend::with_buffer(pixels, (width, band)), size: (width, height), top: top as i32 };
+		draw(backend, top as i32..(top + band) as i32).map_err(|e| Error::plot(path, e))?;
+		stream.write_all(pixels).map_err(|e| Error::io(path, e))?;
+		top += band;
+	}
+	stream.finish().map_err(encode_error)?;
+	writer.finish().map_err(encode_error)
+}
@@ -0,0 +1,105 @@
+use super::{shape_outline, MarkerStyle};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::ops::Range;
+
+// Every marker of a series as a subpath of one filled <path> per colour, at most one per plot-area
+// pixel, with coordinates snapped to whole pixels the way plotters places its circles. Where markers
+// share a pixel the later one wins, and colours appear in the order of their latest marker.
+pub(crate) fn marker_path(
+	points: &[(f64, f64)],
+	style: MarkerStyle<'_>,
+	(x_min, x_max): (f64, f64),
+	(y_min, y_max): (f64, f64),
+	(columns, rows): (Range<i32>, Range<i32>),
+) -> String {
+	let (sx, sy) = ((columns.len().max(1) - 1) as f64 / (x_max - x_min), (rows.len().max(1) - 1) as f64 / (y_max - y_min));
+	let mut pixels: Vec<((i32, i32), usize)> = points
+		.iter()
+		.enumerate()
+		.filter(|(_, (x, y))| (x_min..=x_max).contains(x) && (y_min..=y_max).contains(y))
+		.map(|(i, &(x, y))| ((columns.start + ((x - x_min) * sx).round() as i32, rows.start + ((y_max - y) * sy).round() as i32), i))
+		.collect();
+	pixels.sort_unstable();
+	pixels.reverse();
+	pixels.dedup_by_key(|(pixel, _)| *pixel);
+	let mut groups: HashMap<[u8; 3], Group> = HashMap::new();
+	for ((x, y), i) in pixels.into_iter().rev() {
+		let group = groups.entry(style.fill.at(i)).or_default();
+		group.latest = group.latest.max(i);
+		group.pixels.push((x, y));
+	}
+	let mut groups: Vec<_> = groups.into_iter().collect();
+	groups.sort_unstable_by_key(|(_, group)| group.latest);
+	let radius = style.radius;
+	let outline = shape_outline(style.shape, radius as f64);
+	let opacity = match style.alpha {
+		255 => String::new(),
+		alpha => format!(" fill-opacity=\"{:.3}\"", alpha as f64 / 255.0),
+	};
+	let mut svg = String::new();
+	for ([r, g, b], Group { pixels, .. }) in groups {
+		let mut d = String::with_capacity(pixels.len() * 32);
+		for (x, y) in pixels {
+			match &outline {
+				// Two half-circle arcs from the left edge; overlapping discs stay filled under the nonzero rule.
+				None => {
+					let _ = write!(d, "M{} {y}a{radius} {radius} 0 1 0 {} 0a{radius} {radius} 0 1 0 -{} 0", x - radius, 2 * radius, 2 * radius);
+				}
+				Some(corners) => {
+					for (k, (dx, dy)) in corners.iter().enumerate() {
+						let _ = write!(d, "{}{} {}", if k == 0 { 'M' } else { 'L' }, round(x as f64 + dx), round(y as f64 + dy));
+					}
+					d.push('Z');
+				}
+			}
+		}
+		let _ = writeln!(svg, "<path d=\"{d}\" fill=\"#{r:02x}{g:02x}{b:02x}\"{opacity} stroke=\"none\"/>");
+	}
+	svg
+}
+
+fn round(value: f64) -> f64 {
+	(value * 10.0).round() / 10.0
+}
+
+#[derive(Default)]
+struct Group {
+	latest: usize,
+	pixels: Vec<(i32, i32)>,
+}
+
+// Text the static chart draws in place of its markers, for `replace_placeholder` to swap for the paths.
+pub(crate) const MARKER_PLACEHOLDER: &str = "pendulum-poincare:markers";
+
+// Replaces the <text> element holding the placeholder with `element`. Plotters escapes markup in text
+// and attribute values, so the nearest '<' before the placeholder opens that element.
+pub(crate) fn replace_placeholder(svg: &mut String, element: &str) {
+	let content = format!(">\n{MARKER_PLACEHOLDER}\n</text>\n");
+	let at = svg.rfind(&content).expect("the chart draws the marker placeholder");
+	let start = svg[..at].rfind('<').expect("the placeholder sits in a text element");
+	svg.replace_range(start..at + content.len(), element);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use plotters::prelude::*;
+
+	#[test]
+	fn placeholder_is_replaced_in_place() {
+		let mut svg = String::new();
+		{
+			let area = SVGBackend::with_string(&mut svg, (100, 100)).into_drawing_area();
+			area.draw(&Text::new("<rect> & co", (5, 5), ("sans-serif", 10))).unwrap();
+			area.draw(&Text::new(MARKER_PLACEHOLDER, (0, 0), ("sans-serif", 1))).unwrap();
+			area.draw(&Rectangle::new([(10, 10), (90, 90)], BLACK)).unwrap();
+			area.present().unwrap();
+		}
+		replace_placeholder(&mut svg, "<path d=\"M1 1\"/>\n");
+		assert!(!svg.contains(MARKER_PLACEHOLDER));
+		let (path, rect) = (svg.find("<path").unwrap(), svg.find("<rect").unwrap());
+		assert!(svg.find("&lt;rect&gt; &amp; co").unwrap() < path && path < rect);
+		assert!(svg[..path].ends_with("</text>\n"));
+	}
+}
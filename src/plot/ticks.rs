@@ -0,0 +1,189 @@
+use crate::format::decimal_places;
+use crate::types::TickLabels;
+use plotters::coord::ranged1d::{DefaultFormatting, KeyPointHint, Ranged};
+use std::f64::consts::PI;
+use std::ops::Range;
+
+const MAX_TICKS: usize = 200;
+const AUTO_TICKS: f64 = 10.0;
+
+pub(crate) struct Ticks {
+	pub values: Vec<f64>,
+	pub labels: Vec<String>,
+}
+
+impl Ticks {
+	pub fn label_for(&self, value: f64) -> String {
+		let span = self.values.last().zip(self.values.first()).map_or(1.0, |(last, first)| (last - first).abs().max(1.0));
+		self.values
+			.iter()
+			.position(|tick| (tick - value).abs() <= 1e-9 * span)
+			.map(|i| self.labels[i].clone())
+			.unwrap_or_else(|| format!("{value}"))
+	}
+}
+
+// Plotters' own key-point combinator loses the f64 formatter, which configure_mesh requires.
+pub(crate) struct TickedAxis {
+	min: f64,
+	max: f64,
+	ticks: Vec<f64>,
+}
+
+impl Ticks {
+	pub fn axis(&self, min: f64, max: f64) -> TickedAxis {
+		TickedAxis { min, max, ticks: self.values.clone() }
+	}
+}
+
+impl Ranged for TickedAxis {
+	type FormatOption = DefaultFormatting;
+	type ValueType = f64;
+
+	fn map(&self, value: &f64, limit: (i32, i32)) -> i32 {
+		let fraction = (value - self.min) / (self.max - self.min);
+		limit.0 + (fraction * (limit.1 - limit.0) as f64).round() as i32
+	}
+
+	fn key_points<Hint: KeyPointHint>(&self, hint: Hint) -> Vec<f64> {
+		if hint.max_num_points() == 0 {
+			Vec::new()
+		} else {
+			self.ticks.clone()
+		}
+	}
+
+	fn range(&self) -> Range<f64> {
+		self.min..self.max
+	}
+}
+
+pub(crate) fn axis_ticks(min: f64, max: f64, step: Option<f64>, labels: TickLabels) -> Ticks {
+	let unit = match labels {
+		TickLabels::Numeric => 1.0,
+		TickLabels::Pi => PI,
+	};
+	let (lo, hi) = (min / unit, max / unit);
+	let mut step = match step {
+		Some(step) if step > 0.0 && step.is_finite() => step,
+		_ => auto_step(lo, hi, labels),
+	};
+	while count_multiples(lo, hi, step) > MAX_TICKS {
+		step *= 2.0;
+	}
+	let first = (lo / step - 1e-9).ceil() as i64;
+	let last = (hi / step + 1e-9).floor() as i64;
+	let multiples: Vec<f64> = (first..=last).map(|k| k as f64 * step).collect();
+	let labels = match labels {
+		TickLabels::Numeric => {
+			let decimals = decimal_places(step);
+			multiples.iter().map(|v| format!("{:.*}", decimals, v + 0.0)).collect()
+		}
+		TickLabels::Pi => multiples.iter().map(|&m| pi_label(m)).collect(),
+	};
+	Ticks { values: multiples.iter().map(|m| m * unit).collect(), labels }
+}
+
+fn count_multiples(lo: f64, hi: f64, step: f64) -> usize {
+	((hi / step).floor() - (lo / step).ceil() + 1.0).max(0.0) as usize
+}
+
+fn auto_step(lo: f64, hi: f64, labels: TickLabels) -> f64 {
+	let mut step = match labels {
+		TickLabels::Numeric => 1.0,
+		TickLabels::Pi => 0.5,
+	};
+	let factors = [2.0, 2.5, 2.0];
+	let mut i = 0;
+	while count_multiples(lo, hi, step) as f64 > AUTO_TICKS + 1.0 {
+		step *= factors[i % factors.len()];
+		i += 1;
+	}
+	let mut i = 0;
+	while count_multiples(lo, hi, step) < 2 && hi > lo && i < 60 {
+		step /= factors[i % factors.len()];
+		i += 1;
+	}
+	step
+}
+
+fn pi_label(multiple: f64) -> String {
+	let Some((numerator, denominator)) = (1..=12i64).find_map(|q| {
+		let n = multiple * q as f64;
+		((n - n.round()).abs() < 1e-6).then(|| (n.round() as i64, q))
+	}) else {
+		return format!("{multiple:.3}π");
+	};
+	let divisor = gcd(numerator.abs(), denominator).max(1);
+	let (n, q) = (numerator / divisor, denominator / divisor);
+	let sign = if n < 0 { "−" } else { "" };
+	let coefficient = match n.abs() {
+		0 => return "0".to_string(),
+		1 => String::new(),
+		m => m.to_string(),
+	};
+	if q == 1 {
+		format!("{sign}{coefficient}π")
+	} else {
+		format!("{sign}{coefficient}π/{q}")
+	}
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+	if b == 0 { a } else { gcd(b, a % b) }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn pi_labels() {
+		let cases = [
+			(0.0, "0"),
+			(1.0, "π"),
+			(-1.0, "−π"),
+			(0.5, "π/2"),
+			(-0.5, "−π/2"),
+			(2.0, "2π"),
+			(0.75, "3π/4"),
+			(4.0 / 6.0, "2π/3"),
+			(-1.0 / 12.0, "−π/12"),
+			(0.123, "0.123π"),
+		];
+		for (multiple, label) in cases {
+			assert_eq!(pi_label(multiple), label, "{multiple}");
+		}
+	}
+
+	#[test]
+	fn numeric_ticks_with_a_step() {
+		let ticks = axis_ticks(-1.9, 0.6, Some(0.5), TickLabels::Numeric);
+		assert_eq!(ticks.values, [-1.5, -1.0, -0.5, 0.0, 0.5]);
+		assert_eq!(ticks.labels, ["-1.5", "-1.0", "-0.5", "0.0", "0.5"]);
+		assert_eq!(ticks.label_for(-1.0), "-1.0");
+		// Ticks that land exactly on the range ends are kept.
+		assert_eq!(axis_ticks(0.0, 2.0, Some(1.0), TickLabels::Numeric).labels, ["0", "1", "2"]);
+	}
+
+	#[test]
+	fn pi_ticks_use_units_of_pi() {
+		let ticks = axis_ticks(-3.2, 3.2, Some(0.5), TickLabels::Pi);
+		assert_eq!(ticks.labels, ["−π", "−π/2", "0", "π/2", "π"]);
+		assert_eq!(ticks.values[0], -PI);
+		let auto = axis_ticks(-PI, PI, None, TickLabels::Pi);
+		assert_eq!(auto.labels, ["−π", "−π/2", "0", "π/2", "π"]);
+	}
+
+	#[test]
+	fn automatic_steps_stay_readable() {
+		let ticks = axis_ticks(-4.0, 4.0, None, TickLabels::Numeric);
+		assert_eq!(ticks.values, [-4.0, -3.0, -2.0, -1.0, 0.0, 1.0, 2.0, 3.0, 4.0]);
+		for (min, max) in [(0.0, 1e6), (-0.001, 0.002), (1.9, 3.3)] {
+			let count = axis_ticks(min, max, None, TickLabels::Numeric).values.len();
+			assert!((2..=(AUTO_TICKS as usize + 1)).contains(&count), "{count} ticks on [{min}, {max}]");
+		}
+		// A step far too fine for the range is coarsened instead of producing millions of ticks.
+		assert!(axis_ticks(0.0, 1.0, Some(1e-9), TickLabels::Numeric).values.len() <= MAX_TICKS);
+	}
+}
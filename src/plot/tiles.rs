@@ -0,0 +1,179 @@
+use super::raster::splat_centres;
+use super::colormap::css;
+use super::{
+	background, data_y_range, foreground, marker_alpha, marker_radius, series_colouring, series_style, shape_size, x_label, y_label, Fill,
+	MarkerStyle, Series,
+};
+use crate::error::{Error, Result};
+use crate::output::OUTPUT_DIR;
+use crate::types::{PlotView, TilePyramid};
+use rayon::prelude::*;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const VIEWER: &str = include_str!("tile_viewer.html");
+
+// A marker drawn into one tile, centred in that tile's pixels.
+struct Placed {
+	tile: usize,
+	centre: (f64, f64),
+	series: usize,
+	point: usize,
+}
+
+// Level z splits the plot range into 2^z × 2^z tiles of `tile_px` pixels, as a square image
+// whose level-0 rendering is a single tile. Empty tiles are not written; the manifest lists the rest
+// with a digest of their contents. The pyramid is built beside the old one and then replaces it whole,
+// so no tile of an earlier rendering survives.
+pub fn save_tiles(
+	series: &[Series<'_>],
+	view: &PlotView,
+	out_base: &str,
+	pyramid: &TilePyramid,
+) -> Result<Vec<PathBuf>> {
+	let [x_min, x_max] = view.x_range.unwrap_or([-4.0, 4.0]);
+	let (y_min, y_max) = match view.y_range {
+		Some([y_min, y_max]) => (y_min, y_max),
+		None => data_y_range(series),
+	};
+	let dir = Path::new(OUTPUT_DIR).join(format!("{out_base}__tiles"));
+	let staging = Path::new(OUTPUT_DIR).join(format!("{out_base}__tiles.partial"));
+	remove_dir(&staging)?;
+	let tile = pyramid.tile_px as usize;
+	let radius = view.marker_size.map_or(marker_radius(pyramid.tile_px), |n| n as i32);
+	let styles: Vec<_> = (0..series.len()).map(|index| series_style(view, index, series.len())).collect();
+	let reach = styles.iter().map(|&(_, shape)| shape_size(shape, radius as f64 + 0.5)).fold(0.0, f64::max) + 1.0;
+	let colouring = series_colouring(series, view);
+	let colours: Vec<Vec<[u8; 3]>> = series
+		.iter()
+		.map(|s| colouring.as_ref().zip(s.values).map(|(colouring, values)| colouring.colours(view.colormap, values)).unwrap_or_default())
+		.collect();
+	let visible: Vec<(usize, usize, (f64, f64))> = series
+		.iter()
+		.enumerate()
+		.flat_map(|(index, s)| s.points.iter().copied().enumerate().map(move |(point, xy)| (index, point, xy)))
+		.filter(|(_, _, (x, y))| (x_min..=x_max).contains(x) && (y_min..=y_max).contains(y))
+		.collect();
+	let mut levels = Vec::with_capacity(pyramid.levels as usize);
+	for level in 0..pyramid.levels {
+		let per_axis = 1usize << level;
+		let side = (tile * per_axis) as f64;
+		let (sx, sy) = (side / (x_max - x_min), side / (y_max - y_min));
+		// A marker near a tile edge is drawn into every tile it reaches, in that tile's pixels.
+		let mut placed: Vec<Placed> = visible
+			.par_iter()
+			.flat_map_iter(|&(index, point, (x, y))| {
+				let (px, py) = ((x - x_min) * sx, (y_max - y) * sy);
+				let span = |p: f64| {
+					let first = ((p - reach) / tile as f64).floor().max(0.0) as usize;
+					let last = (((p + reach) / tile as f64).floor() as usize).min(per_axis - 1);
+					first..=last
+				};
+				let (columns, rows) = (span(px), span(py));
+				rows.flat_map(move |ty| {
+					columns.clone().map(move |tx| Placed {
+						tile: ty * per_axis + tx,
+						centre: (px - (tx * tile) as f64, py - (ty * tile) as f64),
+						series: index,
+						point,
+					})
+				})
+			})
+			.collect();
+		// Ordering each tile's markers by series, then point, keeps later ones painted over earlier ones.
+		placed.par_sort_unstable_by_key(|p| (p.tile, p.series, p.point));
+		let groups: Vec<&[Placed]> = placed.chunk_by(|a, b| a.tile == b.tile).collect();
+		let written: Vec<(String, String)> = groups
+			.par_iter()
+			.map(|group| {
+				let (tx, ty) = (group[0].tile % per_axis, group[0].tile / per_axis);
+				let mut pixels = background(view).repeat(tile * tile);
+				for chunk in group.chunk_by(|a, b| a.series == b.series) {
+					let index = chunk[0].series;
+					let centres: Vec<(f64, f64)> = chunk.iter().map(|p| p.centre).collect();
+					let chunk_colours: Vec<[u8; 3]> =
+						chunk.iter().filter_map(|p| colours[index].get(p.point).copied()).collect();
+					let (colour, shape) = styles[index];
+					let fill = if colours[index].is_empty() { Fill::Solid(colour) } else { Fill::Each(&chunk_colours) };
+					let style = MarkerStyle { shape, radius, fill, alpha: marker_alpha(view) };
+					splat_centres(&mut pixels, &centres, style, tile, 0..tile, view.antialias);
+				}
+				let column_dir = staging.join(level.to_string()).join(tx.to_string());
+				std::fs::create_dir_all(&column_dir).map_err(|e| Error::io(&column_dir, e))?;
+				let sha256 = write_tile(&column_dir.join(format!("{ty}.png")), pyramid.tile_px, &pixels)?;
+				Ok((format!("{tx}/{ty}"), sha256))
+			})
+			.collect::<Result<_>>()?;
+		levels.push(written);
+	}
+	// One line per tile in manifest order, so the digest changes with any tile's name or contents.
+	let mut digest = Sha256::new();
+	for (level, tiles) in levels.iter().enumerate() {
+		for (name, sha256) in tiles {
+			digest.update(format!("{level}/{name}.png {sha256}\n"));
+		}
+	}
+	let levels: Vec<Vec<String>> = levels.into_iter().map(|tiles| tiles.into_iter().map(|(name, _)| name).collect()).collect();
+	let manifest = json!({
+		"generator": format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+		"title": view.title,
+		"tile_px": pyramid.tile_px,
+		"levels": pyramid.levels,
+		"x_range": [x_min, x_max],
+		"y_range": [y_min, y_max],
+		"x_label": x_label(view),
+		"y_label": y_label(view),
+		"background": css(background(view)),
+		"foreground": css(foreground(view)),
+		"tiles": levels,
+		"tile_count": levels.iter().map(Vec::len).sum::<usize>(),
+		"tiles_sha256": hex(&digest.finalize()),
+	});
+	let manifest = serde_json::to_string_pretty(&manifest).expect("tile manifest serialises to JSON");
+	std::fs::create_dir_all(&staging).map_err(|e| Error::io(&staging, e))?;
+	let manifest_path = staging.join("manifest.json");
+	std::fs::write(&manifest_path, &manifest).map_err(|e| Error::io(&manifest_path, e))?;
+	// The manifest is inlined because browsers refuse to fetch it from a file:// page.
+	let viewer_path = staging.join("index.html");
+	let viewer = VIEWER.replace("/*MANIFEST*/null", &manifest.replace("</", "<\\/")).replace("<!--TITLE-->", &escape_html(&view.title));
+	std::fs::write(&viewer_path, viewer).map_err(|e| Error::io(&viewer_path, e))?;
+	remove_dir(&dir)?;
+	std::fs::rename(&staging, &dir).map_err(|e| Error::io(&dir, e))?;
+	Ok(vec![dir.join("manifest.json"), dir.join("index.html")])
+}
+
+fn remove_dir(path: &Path) -> Result<()> {
+	match std::fs::remove_dir_all(path) {
+		Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(Error::io(path, e)),
+		_ => Ok(()),
+	}
+}
+
+// Writes the tile and returns the SHA-256 of the PNG as hex.
+fn write_tile(path: &Path, tile_px: u32, pixels: &[u8]) -> Result<String> {
+	let mut bytes = Vec::new();
+	let mut encoder = png::Encoder::new(&mut bytes, tile_px, tile_px);
+	encoder.set_color(png::ColorType::Rgb);
+	encoder.set_depth(png::BitDepth::Eight);
+	let encode_error = |e: png::EncodingError| match e {
+		png::EncodingError::IoError(e) => Error::io(path, e),
+		e => Error::plot(path, e),
+	};
+	let mut writer = encoder.write_header().map_err(encode_error)?;
+	writer.write_image_data(pixels).map_err(encode_error)?;
+	writer.finish().map_err(encode_error)?;
+	let mut file = File::create(path).map_err(|e| Error::io(path, e))?;
+	file.write_all(&bytes).map_err(|e| Error::io(path, e))?;
+	Ok(hex(&Sha256::digest(&bytes)))
+}
+
+fn hex(bytes: &[u8]) -> String {
+	bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn escape_html(text: &str) -> String {
+	text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
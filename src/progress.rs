@@ -0,0 +1,96 @@
+use crate::simulate::{Control, Progress, RunObserver};
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const BAR_WIDTH: usize = 30;
+const REDRAW_INTERVAL: Duration = Duration::from_millis(200);
+
+pub struct ConsoleProgress {
+	cancel: Arc<AtomicBool>,
+	interactive: bool,
+	last_draw: Option<Instant>,
+	last_decile: usize,
+	drawn: bool,
+}
+
+impl ConsoleProgress {
+	pub fn new(cancel: Arc<AtomicBool>) -> Self {
+		Self {
+			cancel,
+			interactive: std::io::stderr().is_terminal(),
+			last_draw: None,
+			last_decile: 0,
+			drawn: false,
+		}
+	}
+
+	pub fn finish(&mut self) {
+		if self.interactive && self.drawn {
+			eprintln!();
+		}
+		self.drawn = false;
+	}
+
+	fn draw(&mut self, progress: &Progress) {
+		let fraction = progress.fraction().clamp(0.0, 1.0);
+		let filled = (fraction * BAR_WIDTH as f64).round() as usize;
+		let eta = progress.eta.map(format_duration).unwrap_or_else(|| "--".to_string());
+		let line = format!(
+			"[{}{}] {:5.1}%  {}/{} periods  dt={:.3e}  elapsed {}  ETA {}",
+			"#".repeat(filled),
+			" ".repeat(BAR_WIDTH - filled),
+			fraction * 100.0,
+			progress.periods_done,
+			progress.periods_total,
+			progress.dt,
+			format_duration(progress.elapsed),
+			eta,
+		);
+		let mut stderr = std::io::stderr().lock();
+		if self.interactive {
+			let _ = write!(stderr, "\r{line}\x1b[K");
+		} else {
+			let _ = writeln!(stderr, "{line}");
+		}
+		let _ = stderr.flush();
+		self.drawn = true;
+	}
+}
+
+impl RunObserver for ConsoleProgress {
+	fn on_progress(&mut self, progress: &Progress) -> Control {
+		let done = progress.periods_done >= progress.periods_total;
+		if self.interactive {
+			let due = self.last_draw.is_none_or(|t| t.elapsed() >= REDRAW_INTERVAL);
+			if due || done {
+				self.draw(progress);
+				self.last_draw = Some(Instant::now());
+			}
+		} else {
+			let decile = (progress.fraction() * 10.0).floor() as usize;
+			if decile > self.last_decile {
+				self.last_decile = decile;
+				self.draw(progress);
+			}
+		}
+		if self.cancel.load(Ordering::Relaxed) {
+			Control::Cancel
+		} else {
+			Control::Continue
+		}
+	}
+}
+
+fn format_duration(d: Duration) -> String {
+	let secs = d.as_secs();
+	let (h, m, s) = (secs / 3600, (secs / 60) % 60, secs % 60);
+	if h > 0 {
+		format!("{h}h{m:02}m{s:02}s")
+	} else if m > 0 {
+		format!("{m}m{s:02}s")
+	} else {
+		format!("{s}s")
+	}
+}
@@ -0,0 +1,65 @@
+use crate::error::{Error, Result};
+use crate::output::output_file;
+use crate::simulate::RunOutcome;
+use crate::types::RunSpec;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// `inputs` lists the saved sample files a figure was re-plotted from; it is empty for fresh runs.
+pub fn write_meta(spec: &RunSpec, outcome: &RunOutcome, inputs: &[PathBuf], outputs: &[PathBuf]) -> Result<PathBuf> {
+	let describe = |paths: &[PathBuf], full_path: bool| {
+		paths
+			.iter()
+			.map(|path| {
+				let (sha256, bytes) = sha256_file(path)?;
+				let name = match path.file_name() {
+					Some(name) if !full_path => name.to_string_lossy(),
+					_ => path.to_string_lossy(),
+				};
+				Ok(json!({ "file": name, "bytes": bytes, "sha256": sha256 }))
+			})
+			.collect::<Result<Vec<Value>>>()
+	};
+	let created = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+	let meta = json!({
+		"generator": { "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") },
+		"created_unix": created,
+		"host": {
+			"threads": std::thread::available_parallelism().map_or(1, |n| n.get()),
+			"os": std::env::consts::OS,
+			"arch": std::env::consts::ARCH,
+		},
+		"integrator": { "method": spec.integrator.method, "stats": outcome.stats },
+		"wall_seconds": outcome.stats.wall_seconds,
+		"samples": outcome.samples.len(),
+		"cancelled": outcome.cancelled,
+		"inputs": describe(inputs, true)?,
+		"outputs": describe(outputs, false)?,
+		"spec": spec,
+	});
+	let path = output_file(&spec.output.out_base, "meta.json")?;
+	let contents = serde_json::to_string_pretty(&meta).expect("metadata is valid JSON");
+	std::fs::write(&path, contents).map_err(|e| Error::io(&path, e))?;
+	Ok(path)
+}
+
+pub fn sha256_file(path: &Path) -> Result<(String, u64)> {
+	let mut file = File::open(path).map_err(|e| Error::io(path, e))?;
+	let mut hasher = Sha256::new();
+	let mut buffer = vec![0u8; 1 << 16];
+	let mut total = 0u64;
+	loop {
+		let n = file.read(&mut buffer).map_err(|e| Error::io(path, e))?;
+		if n == 0 {
+			break;
+		}
+		hasher.update(&buffer[..n]);
+		total += n as u64;
+	}
+	let hex = hasher.finalize().iter().map(|b| format!("{b:02x}")).collect();
+	Ok((hex, total))
+}
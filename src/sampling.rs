@@ -4,12 +4,13 @@ use crate::types::*;
 pub struct PoincareSampler {
     pub k: usize,
     pub counter: usize,
+    pub periods_before: usize,
     pub wrap_to_pi: bool,
 }
 
 impl PoincareSampler {
-    pub fn new(k: usize, wrap_to_pi: bool) -> Self {
-        Self { k, counter: 0, wrap_to_pi }
+    pub fn new(k: usize, periods_before: usize, wrap_to_pi: bool) -> Self {
+        Self { k, counter: 0, periods_before, wrap_to_pi }
     }
 
     pub fn reset(&mut self) {
@@ -18,7 +19,7 @@ impl PoincareSampler {
 
     pub fn should_record(&mut self) -> bool {
         self.counter = self.counter.wrapping_add(1);
-        self.counter % self.k == 0
+        self.counter.is_multiple_of(self.k)
     }
 
     pub fn on_sample(&self, state: &State) -> SamplePoint {
@@ -27,20 +28,22 @@ impl PoincareSampler {
         } else {
             state.theta
         };
-        SamplePoint { theta, omega: state.omega }
+        let period = self.periods_before + self.counter / self.k;
+        sample_point(period, state, theta)
     }
 }
 
 pub struct TimeGridSampler {
     pub t_next: f64,
     pub period: f64,
+    pub period_next: usize,
     pub wrap_to_pi: bool,
 }
 
 impl TimeGridSampler {
     pub fn new(t0: f64, periods_to_skip: usize, period: f64, wrap_to_pi: bool) -> Self {
         let start = t0 + (periods_to_skip as f64 + 1.0) * period;
-        Self { t_next: start, period, wrap_to_pi }
+        Self { t_next: start, period, period_next: periods_to_skip + 1, wrap_to_pi }
     }
 
     pub fn target_time(&self) -> f64 {
@@ -49,10 +52,15 @@ impl TimeGridSampler {
 
     pub fn advance(&mut self) {
         self.t_next += self.period;
+        self.period_next += 1;
     }
 
     pub fn on_sample(&self, state: &State) -> SamplePoint {
         let theta = if self.wrap_to_pi { wrap_angle_pi(state.theta) } else { state.theta };
-        SamplePoint { theta, omega: state.omega }
+        sample_point(self.period_next, state, theta)
     }
 }
+
+fn sample_point(period: usize, state: &State, theta: f64) -> SamplePoint {
+    SamplePoint { period, t: state.t, theta, theta_unwrapped: state.theta, omega: state.omega }
+}
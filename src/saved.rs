@@ -0,0 +1,175 @@
+use crate::container::read_container;
+use crate::dynamics::wrap_angle_pi;
+use crate::error::{Error, Result};
+use crate::export::CSV_COLUMNS;
+use crate::simulate::RunOutcome;
+use crate::types::{IntegratorStats, SamplePoint};
+use serde_json::Value;
+use std::path::Path;
+
+pub struct SavedRun {
+	pub spec: Value,
+	pub outcome: RunOutcome,
+}
+
+impl SavedRun {
+	pub fn set_wrap_to_pi(&mut self, wrap_to_pi: bool) {
+		for s in &mut self.outcome.samples {
+			s.theta = if wrap_to_pi { wrap_angle_pi(s.theta_unwrapped) } else { s.theta_unwrapped };
+		}
+	}
+}
+
+pub fn load_saved_run(path: &str) -> Result<SavedRun> {
+	let extension = Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+	match extension.as_str() {
+		"ppb" => {
+			let container = read_container(Path::new(path))?;
+			let spec = serde_json::to_value(&container.spec).expect("run spec serialises to JSON");
+			let (_, outcome) = container.into_outcome();
+			Ok(SavedRun { spec, outcome })
+		}
+		"csv" => {
+			let contents = std::fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+			parse_csv(&contents).map_err(|message| Error::parse(path, message))
+		}
+		_ => Err(Error::parse(path, "saved samples must be a .csv or .ppb file written by run")),
+	}
+}
+
+fn parse_csv(contents: &str) -> std::result::Result<SavedRun, String> {
+	let mut lines = contents.lines().enumerate();
+	let mut spec_lines = Vec::new();
+	let mut in_spec = false;
+	let mut cancelled = false;
+	let header = loop {
+		let Some((_, line)) = lines.next() else {
+			return Err("no column header line".to_string());
+		};
+		let Some(comment) = line.strip_prefix('#') else {
+			break line;
+		};
+		let comment = comment.strip_prefix(' ').unwrap_or(comment);
+		if in_spec {
+			spec_lines.push(comment);
+		} else if comment == "spec:" {
+			in_spec = true;
+		} else if comment.starts_with("samples:") && comment.ends_with("(cancelled)") {
+			cancelled = true;
+		}
+	};
+	if header.trim() != CSV_COLUMNS.join(",") {
+		return Err(format!("expected columns {}, found {header:?}", CSV_COLUMNS.join(",")));
+	}
+	if spec_lines.is_empty() {
+		return Err("the comment header does not contain the run spec".to_string());
+	}
+	let spec: Value = serde_json::from_str(&spec_lines.join("\n")).map_err(|e| format!("embedded spec: {e}"))?;
+	let mut samples = Vec::new();
+	for (index, line) in lines {
+		if line.trim().is_empty() {
+			continue;
+		}
+		let fields: Vec<&str> = line.split(',').collect();
+		let number = |i: usize| {
+			fields
+				.get(i)
+				.and_then(|f| f.trim().parse::<f64>().ok())
+				.ok_or_else(|| format!("line {}: column {} is not a number", index + 1, CSV_COLUMNS[i]))
+		};
+		if fields.len() != CSV_COLUMNS.len() {
+			return Err(format!("line {}: expected {} columns, found {}", index + 1, CSV_COLUMNS.len(), fields.len()));
+		}
+		samples.push(SamplePoint {
+			period: number(0)? as usize,
+			t: number(1)?,
+			theta: number(2)?,
+			theta_unwrapped: number(3)?,
+			omega: number(4)?,
+		});
+	}
+	let mut saved = SavedRun { spec, outcome: RunOutcome { samples, cancelled, stats: IntegratorStats::default() } };
+	let wrap = saved.spec.pointer("/poincare/wrap_to_pi").and_then(Value::as_bool).unwrap_or(true);
+	saved.set_wrap_to_pi(wrap);
+	Ok(saved)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::container::write_container;
+	use crate::export::write_csv_to;
+	use crate::test_support::{outcome, spec};
+	use crate::types::RunSpec;
+
+	fn csv(spec: &RunSpec, outcome: &RunOutcome) -> String {
+		let mut bytes = Vec::new();
+		write_csv_to(&mut bytes, spec, outcome).unwrap();
+		String::from_utf8(bytes).unwrap()
+	}
+
+	fn assert_same_samples(read: &[SamplePoint], written: &[SamplePoint]) {
+		assert_eq!(read.len(), written.len());
+		for (r, w) in read.iter().zip(written) {
+			assert_eq!(r.period, w.period);
+			assert_eq!((r.t, r.theta, r.theta_unwrapped, r.omega), (w.t, w.theta, w.theta_unwrapped, w.omega));
+		}
+	}
+
+	#[test]
+	fn csv_header_layout() {
+		let mut cancelled = outcome(2);
+		cancelled.cancelled = true;
+		let text = csv(&spec(), &cancelled);
+		let mut lines = text.lines();
+		assert_eq!(lines.next(), Some(concat!("# ", env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"), " Poincaré samples")));
+		assert_eq!(lines.next(), Some("# samples: 2 (cancelled)"));
+		assert_eq!(lines.next(), Some("# spec:"));
+		assert_eq!(lines.next(), Some("# {"));
+		let header = lines.find(|l| !l.starts_with('#')).unwrap();
+		assert_eq!(header, "period,t,theta_wrapped,theta_unwrapped,omega");
+		assert_eq!(lines.count(), 2);
+	}
+
+	#[test]
+	fn csv_round_trip() {
+		let written = outcome(50);
+		let saved = parse_csv(&csv(&spec(), &written)).unwrap();
+		assert_eq!(saved.spec, serde_json::to_value(spec()).unwrap());
+		assert!(!saved.outcome.cancelled);
+		assert_same_samples(&saved.outcome.samples, &written.samples);
+
+		let mut unwrapped = spec();
+		unwrapped.poincare.wrap_to_pi = false;
+		let saved = parse_csv(&csv(&unwrapped, &written)).unwrap();
+		assert!(saved.outcome.samples.iter().all(|s| s.theta == s.theta_unwrapped));
+	}
+
+	#[test]
+	fn csv_errors() {
+		let text = csv(&spec(), &outcome(3));
+		assert!(parse_csv(&text.replace("theta_wrapped", "theta")).err().unwrap().starts_with("expected columns"));
+		let without_spec: String = text.lines().skip_while(|l| l.starts_with('#')).map(|l| format!("{l}\n")).collect();
+		assert_eq!(parse_csv(&without_spec).err().unwrap(), "the comment header does not contain the run spec");
+		let short_row = format!("{}1,2,3\n", text);
+		assert!(parse_csv(&short_row).err().unwrap().contains("expected 5 columns, found 3"));
+	}
+
+	#[test]
+	fn load_saved_run_by_extension() {
+		let dir = std::env::temp_dir().join(format!("pendulum-poincare-saved-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		let written = outcome(20);
+		let csv_path = dir.join("run.csv");
+		std::fs::write(&csv_path, csv(&spec(), &written)).unwrap();
+		let ppb_path = dir.join("run.PPB");
+		write_container(&ppb_path, &spec(), &written, true).unwrap();
+		for path in [&csv_path, &ppb_path] {
+			let saved = load_saved_run(path.to_str().unwrap()).unwrap();
+			assert_eq!(saved.spec["output"]["out_base"], "t");
+			assert_same_samples(&saved.outcome.samples, &written.samples);
+		}
+		assert!(matches!(load_saved_run(dir.join("run.npy").to_str().unwrap()), Err(Error::Parse { .. })));
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+}
@@ -0,0 +1,561 @@
+use crate::settings::{
+	DEFAULT_ATOL,
+	DEFAULT_AXIS_LABEL_FONT_PX,
+	DEFAULT_RTOL,
+	DEFAULT_TICK_FONT_PX,
+	DEFAULT_TITLE_FONT_PX,
+	MAX_TILE_LEVELS,
+	MAX_TILE_PX,
+	MIN_AXIS_LABEL_FONT_PX,
+	MIN_SIDE_PX,
+	MIN_TICK_FONT_PX,
+	MIN_TILE_PX,
+	MIN_TITLE_FONT_PX,
+};
+use serde_json::{json, Value};
+
+pub fn run_spec_schema() -> Value {
+	let mut schema = base_schema();
+	allow_sweeps(&mut schema);
+	schema["$defs"]["Sweep"] = json!({
+		"description": "Expands the run into one run per value; see batch expansion.",
+		"oneOf": [
+			{ "type": "array", "items": { "type": "number" }, "minItems": 1 },
+			{
+				"type": "object",
+				"required": ["range"],
+				"additionalProperties": false,
+				"properties": {
+					"range": {
+						"type": "array",
+						"items": { "type": "number" },
+						"minItems": 3,
+						"maxItems": 3,
+						"description": "[start, end, step], end inclusive."
+					}
+				}
+			}
+		]
+	});
+	schema
+}
+
+pub(crate) fn base_schema() -> Value {
+	json!({
+		"$schema": "https://json-schema.org/draft/2020-12/schema",
+		"title": "RunSpec",
+		"description": "Driven damped pendulum Poincaré section run specification.",
+		"type": "object",
+		"required": ["phys", "integrator", "init", "plot", "output"],
+		"additionalProperties": false,
+		"properties": {
+			"phys": { "$ref": "#/$defs/PhysicalParams" },
+			"integrator": { "$ref": "#/$defs/IntegratorParams" },
+			"init": { "$ref": "#/$defs/InitialState" },
+			"poincare": { "$ref": "#/$defs/PoincareConfig" },
+			"plot": { "$ref": "#/$defs/PlotView" },
+			"output": { "$ref": "#/$defs/OutputConfig" }
+		},
+		"$defs": {
+			"PhysicalParams": physical_params(),
+			"IntegratorMethod": {
+				"enum": ["EulerCromer", "RK4", "RK45", "BulirschStoer"],
+				"description": "EulerCromer and RK4 use a fixed step; RK45 and BulirschStoer are adaptive."
+			},
+			"IntegratorParams": integrator_params(),
+			"InitialState": initial_state(),
+			"PoincareConfig": {
+				"type": "object",
+				"additionalProperties": false,
+				"properties": {
+					"wrap_to_pi": {
+						"type": "boolean",
+						"default": true,
+						"description": "Fold sampled θ into (−π, π]."
+					}
+				}
+			},
+			"PlotView": plot_view(),
+			"Region": region(),
+			"SeriesStyle": {
+				"type": "object",
+				"additionalProperties": false,
+				"properties": {
+					"label": { "type": ["string", "null"], "description": "Legend text; defaults to the data file name." },
+					"colour": colour("Marker colour as #rrggbb or #rgb; ignored when plot.colour_by is set."),
+					"marker": { "enum": ["circle", "square", "diamond", "triangle"], "default": "circle" }
+				}
+			},
+			"TilePyramid": {
+				"type": "object",
+				"additionalProperties": false,
+				"properties": {
+					"levels": {
+						"type": "integer",
+						"minimum": 1,
+						"maximum": MAX_TILE_LEVELS,
+						"default": 6,
+						"description": "Zoom levels; level z has 2^z × 2^z tiles."
+					},
+					"tile_px": {
+						"type": "integer",
+						"minimum": MIN_TILE_PX,
+						"maximum": MAX_TILE_PX,
+						"default": 256,
+						"description": "Side of each square tile in pixels."
+					}
+				}
+			},
+			"OutputConfig": {
+				"type": "object",
+				"required": ["out_base"],
+				"additionalProperties": false,
+				"properties": {
+					"out_base": {
+						"type": "string",
+						"default": "poincare",
+						"description": "File name prefix under output/; an empty value falls back to the default."
+					},
+					"data": {
+						"type": "array",
+						"items": { "enum": ["csv", "npy", "npz", "ppb"] },
+						"uniqueItems": true,
+						"default": ["csv"],
+						"description": "Sample files written by run and sweep: <out_base>.csv, a (samples, 5) float64 <out_base>.npy, an <out_base>.npz of named columns, or the checksummed <out_base>.ppb container that embeds the spec."
+					},
+					"compress": {
+						"type": "boolean",
+						"default": false,
+						"description": "Deflate the sample blocks of the .ppb container."
+					}
+				}
+			}
+		}
+	})
+}
+
+fn allow_sweeps(schema: &mut Value) {
+	let mut pending = ref_targets(schema.get("properties"));
+	let mut reachable = Vec::new();
+	while let Some(name) = pending.pop() {
+		if !reachable.contains(&name) {
+			pending.extend(ref_targets(schema.pointer(&format!("/$defs/{name}/properties"))));
+			reachable.push(name);
+		}
+	}
+	for name in reachable {
+		let Some(properties) = schema
+			.pointer_mut(&format!("/$defs/{name}/properties"))
+			.and_then(Value::as_object_mut)
+		else {
+			continue;
+		};
+		for property in properties.values_mut() {
+			if is_numeric(property.get("type")) {
+				let single = property.take();
+				*property = json!({ "anyOf": [single, { "$ref": "#/$defs/Sweep" }] });
+			}
+		}
+	}
+}
+
+fn ref_targets(properties: Option<&Value>) -> Vec<String> {
+	properties
+		.and_then(Value::as_object)
+		.into_iter()
+		.flat_map(|map| map.values())
+		.flat_map(|p| std::iter::once(p).chain(p.get("anyOf").and_then(Value::as_array).into_iter().flatten()))
+		.filter_map(|p| p.get("$ref").and_then(Value::as_str))
+		.filter_map(|r| r.strip_prefix("#/$defs/"))
+		.map(str::to_string)
+		.collect()
+}
+
+pub(crate) fn is_numeric(ty: Option<&Value>) -> bool {
+	let is_number = |v: &Value| matches!(v.as_str(), Some("number") | Some("integer"));
+	match ty {
+		Some(Value::Array(types)) => types.iter().any(is_number),
+		Some(v) => is_number(v),
+		None => false,
+	}
+}
+
+pub(crate) fn resolve_ref<'a>(node: &'a Value, root: &'a Value) -> &'a Value {
+	match node.get("$ref").and_then(Value::as_str) {
+		Some(reference) => reference
+			.strip_prefix('#')
+			.and_then(|pointer| root.pointer(pointer))
+			.unwrap_or(node),
+		None => node,
+	}
+}
+
+// The object schema a property stands for: its `$ref` target, or the branch of an `anyOf` that has
+// properties, as for an optional struct's `[{ "$ref": ... }, { "type": "null" }]`.
+pub(crate) fn object_schema<'a>(node: &'a Value, root: &'a Value) -> &'a Value {
+	let node = resolve_ref(node, root);
+	node.get("anyOf")
+		.and_then(Value::as_array)
+		.and_then(|branches| branches.iter().map(|b| resolve_ref(b, root)).find(|b| b.get("properties").is_some()))
+		.unwrap_or(node)
+}
+
+pub(crate) fn schema_allows(node: &Value, ty: &str) -> bool {
+	match node.get("type") {
+		Some(Value::Array(types)) => types.iter().any(|t| t.as_str() == Some(ty)),
+		Some(t) => t.as_str() == Some(ty),
+		None => false,
+	}
+}
+
+pub(crate) fn property_schema(path: &str) -> Option<Value> {
+	let root = base_schema();
+	let mut node = &root;
+	for key in path.split('.') {
+		node = object_schema(node, &root).get("properties")?.get(key)?;
+	}
+	Some(resolve_ref(node, &root).clone())
+}
+
+fn physical_params() -> Value {
+	json!({
+		"type": "object",
+		"required": ["g", "l", "q", "f_drive", "omega_d"],
+		"additionalProperties": false,
+		"properties": {
+			"g": { "type": "number", "exclusiveMinimum": 0, "description": "Gravitational acceleration." },
+			"l": { "type": "number", "exclusiveMinimum": 0, "description": "Pendulum length." },
+			"q": { "type": "number", "minimum": 0, "description": "Damping coefficient." },
+			"f_drive": { "type": "number", "description": "Drive amplitude." },
+			"omega_d": { "type": "number", "exclusiveMinimum": 0, "description": "Drive angular frequency." }
+		}
+	})
+}
+
+fn integrator_params() -> Value {
+	json!({
+		"type": "object",
+		"required": ["method", "n_periods_warmup", "n_periods_samples"],
+		"additionalProperties": false,
+		"properties": {
+			"method": { "$ref": "#/$defs/IntegratorMethod" },
+			"dt_user": {
+				"type": ["number", "null"],
+				"description": "Fixed-step size, snapped to an even number of steps per drive period. Ignored by adaptive methods; chosen automatically when absent or not positive."
+			},
+			"n_periods_warmup": { "type": "integer", "minimum": 0, "description": "Drive periods integrated before sampling." },
+			"n_periods_samples": { "type": "integer", "minimum": 1, "description": "Drive periods sampled, one point each." },
+			"rtol": {
+				"type": ["number", "null"],
+				"default": DEFAULT_RTOL,
+				"description": "Relative tolerance of adaptive methods; non-positive values fall back to the default."
+			},
+			"atol": {
+				"type": ["number", "null"],
+				"default": DEFAULT_ATOL,
+				"description": "Absolute tolerance of adaptive methods; non-positive values fall back to the default."
+			},
+			"dt_init": {
+				"type": ["number", "null"],
+				"description": "Initial adaptive step; defaults to drive period / 400 when absent or not positive and is clamped to [dt_min, dt_max]."
+			},
+			"dt_min": {
+				"type": ["number", "null"],
+				"description": "Smallest adaptive step; defaults to drive period / 20000 when absent or not positive and must not exceed dt_max."
+			},
+			"dt_max": {
+				"type": ["number", "null"],
+				"description": "Largest adaptive step; defaults to drive period / 20 when absent or not positive."
+			}
+		}
+	})
+}
+
+fn initial_state() -> Value {
+	json!({
+		"type": "object",
+		"required": ["theta0", "omega0", "t0"],
+		"additionalProperties": false,
+		"properties": {
+			"theta0": { "type": "number", "description": "Initial angle in radians." },
+			"omega0": { "type": "number", "description": "Initial angular velocity in radians/s." },
+			"t0": { "type": "number", "description": "Initial time." }
+		}
+	})
+}
+
+fn plot_view() -> Value {
+	let range = |description: &str| {
+		json!({
+			"anyOf": [
+				{ "type": "array", "items": { "type": "number" }, "minItems": 2, "maxItems": 2 },
+				{ "type": "null" }
+			],
+			"description": description
+		})
+	};
+	let step = |description: &str| json!({ "type": ["number", "null"], "exclusiveMinimum": 0, "description": description });
+	let labels = json!({
+		"enum": ["numeric", "pi"],
+		"default": "numeric",
+		"description": "pi labels ticks as multiples of π, and the tick step is then given in units of π."
+	});
+	json!({
+		"type": "object",
+		"required": ["side_px", "title"],
+		"additionalProperties": false,
+		"properties": {
+			"side_px": { "type": "integer", "minimum": MIN_SIDE_PX, "description": "Canvas side length in pixels." },
+			"width_px": { "type": ["integer", "null"], "minimum": MIN_SIDE_PX, "description": "Canvas width; defaults to side_px." },
+			"height_px": {
+				"type": ["integer", "null"],
+				"minimum": MIN_SIDE_PX,
+				"description": "Canvas height; defaults to side_px. Ignored when aspect is \"equal\" or aspect_ratio is set."
+			},
+			"aspect": {
+				"enum": ["free", "equal"],
+				"default": "free",
+				"description": "equal derives the height from the width so that a unit of θ and of ω span the same length on screen."
+			},
+			"aspect_ratio": {
+				"type": ["number", "null"],
+				"exclusiveMinimum": 0,
+				"description": "Canvas width divided by height; the height follows from the width."
+			},
+			"dpi": {
+				"type": ["number", "null"],
+				"exclusiveMinimum": 0,
+				"description": "Reads font, marker and margin sizes as CSS pixels (1/96 inch) and scales them by dpi/96; the PNG records the resolution."
+			},
+			"title": { "type": "string" },
+			"marker_size": {
+				"type": ["integer", "null"],
+				"minimum": 0,
+				"description": "Marker radius in pixels, scaled by dpi; automatic when absent or 0."
+			},
+			"title_font_px": {
+				"type": ["integer", "null"],
+				"minimum": 0,
+				"default": DEFAULT_TITLE_FONT_PX,
+				"description": format!("Values below {MIN_TITLE_FONT_PX} are replaced by the default.")
+			},
+			"axis_label_font_px": {
+				"type": ["integer", "null"],
+				"minimum": 0,
+				"default": DEFAULT_AXIS_LABEL_FONT_PX,
+				"description": format!("Values below {MIN_AXIS_LABEL_FONT_PX} are replaced by the default.")
+			},
+			"tick_font_px": {
+				"type": ["integer", "null"],
+				"minimum": 0,
+				"default": DEFAULT_TICK_FONT_PX,
+				"description": format!("Values below {MIN_TICK_FONT_PX} are replaced by the default.")
+			},
+			"x_range": range("θ axis [min, max]; defaults to [-4, 4]."),
+			"y_range": range("ω axis [min, max]; fitted to the data when absent."),
+			"x_tick_step": step("Spacing of θ ticks; chosen from the range when absent."),
+			"y_tick_step": step("Spacing of ω ticks; chosen from the range when absent."),
+			"x_tick_labels": labels,
+			"y_tick_labels": labels,
+			"render": {
+				"enum": ["points", "density"],
+				"default": "points",
+				"description": "density bins the samples per pixel and colours the counts, with a colourbar."
+			},
+			"antialias": {
+				"type": "boolean",
+				"default": false,
+				"description": "Smooth marker edges in the PNG; markers are otherwise hard-edged discs."
+			},
+			"svg_points": {
+				"enum": ["circles", "path", "raster"],
+				"default": "circles",
+				"description": "SVG markers: one <circle> per sample, one path with a disc per occupied pixel, or an embedded PNG layer under vector axes and text."
+			},
+			"strip_rows": {
+				"type": ["integer", "null"],
+				"minimum": 0,
+				"description": "Canvas rows rendered and encoded at a time for the PNG; bands of about 64 MiB when absent or 0."
+			},
+			"html_point_budget": {
+				"type": ["integer", "null"],
+				"minimum": 1,
+				"description": "Thin the HTML scatter to one point per pixel, coarsening the grid until at most this many remain; every point is kept when absent."
+			},
+			"colormap": {
+				"enum": ["viridis", "magma", "inferno", "plasma", "greys"],
+				"default": "viridis",
+				"description": "Colour scale for density plots and coloured points."
+			},
+			"density_scale": {
+				"enum": ["log", "linear"],
+				"default": "log",
+				"description": "Mapping from bin counts to the colour scale."
+			},
+			"colour_by": {
+				"enum": ["none", "period", "ftle", "energy", "rotation"],
+				"default": "none",
+				"description": "Colour points by drive period index, local finite-time Lyapunov exponent over the next period, energy per unit mass, or net rotation direction between samples, with a colourbar."
+			},
+			"series": {
+				"type": "array",
+				"items": { "$ref": "#/$defs/SeriesStyle" },
+				"default": [],
+				"description": "Style of each overlaid series, in --data order; unlisted series take the next palette colour and circles."
+			},
+			"legend": {
+				"enum": ["upper_right", "upper_left", "lower_left", "lower_right", "none"],
+				"default": "upper_right",
+				"description": "Corner of the plot holding the legend, shown when more than one series is drawn."
+			},
+			"theme": {
+				"enum": ["light", "dark"],
+				"default": "light",
+				"description": "Default background and foreground: black on white, or light grey on near-black."
+			},
+			"background": colour("Page and plot-area colour; defaults to the theme's."),
+			"foreground": colour("Colour of text, axes, frame and a lone series' markers; defaults to the theme's."),
+			"marker_colour": colour("Marker colour when one series is drawn; defaults to the foreground."),
+			"marker_opacity": {
+				"type": ["number", "null"],
+				"minimum": 0,
+				"maximum": 1,
+				"description": "Marker opacity in every output; markers are opaque in PNG/SVG and 0.8 in HTML when absent."
+			},
+			"font_family": {
+				"type": ["string", "null"],
+				"default": "sans-serif",
+				"description": "Font for all text; pick one covering the script of non-Latin titles and labels."
+			},
+			"x_label": { "type": ["string", "null"], "default": "θ (radians)", "description": "Horizontal axis title." },
+			"y_label": { "type": ["string", "null"], "default": "ω (radians/s)", "description": "Vertical axis title." },
+			"frame_width": {
+				"type": ["integer", "null"],
+				"minimum": 1,
+				"default": 2,
+				"description": "Line width of the plot frame, axes and colourbar outline in pixels, scaled by dpi."
+			},
+			"regions": {
+				"type": "array",
+				"items": { "$ref": "#/$defs/Region" },
+				"default": [],
+				"description": "Zoomed views, each written as output/<out_base>__<name>.{png,svg,html}."
+			},
+			"tiles": {
+				"anyOf": [{ "$ref": "#/$defs/TilePyramid" }, { "type": "null" }],
+				"description": "Deep-zoom tile pyramid with an offline viewer under output/<out_base>__tiles/."
+			}
+		}
+	})
+}
+
+fn colour(description: &str) -> Value {
+	json!({ "type": ["string", "null"], "pattern": "^#([0-9A-Fa-f]{3}|[0-9A-Fa-f]{6})$", "description": description })
+}
+
+fn region() -> Value {
+	let bounds = json!({
+		"type": "array",
+		"items": { "type": "number" },
+		"minItems": 2,
+		"maxItems": 2,
+		"description": "[min, max] with min < max."
+	});
+	json!({
+		"type": "object",
+		"required": ["name", "theta"],
+		"additionalProperties": false,
+		"properties": {
+			"name": { "type": "string", "pattern": "^[A-Za-z0-9_.=-]+$", "description": "File name suffix." },
+			"theta": bounds,
+			"omega": {
+				"anyOf": [bounds, { "type": "null" }],
+				"description": "Restricts the points and fixes the ω axis; autoscaled when absent."
+			},
+			"title": { "type": ["string", "null"], "description": "Defaults to plot.title." },
+			"side_px": {
+				"type": ["integer", "null"],
+				"minimum": MIN_SIDE_PX,
+				"description": "Defaults to plot.side_px; replaces plot.width_px and plot.height_px when given."
+			}
+		}
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_support::spec_value;
+	use crate::types::{PlotView, RunSpec};
+
+	// Fills the optional nested tables so that their types are walked too.
+	fn nested_spec_value() -> Value {
+		let mut value = spec_value();
+		value["plot"]["series"] = json!([{}]);
+		value["plot"]["regions"] = json!([{ "name": "z", "theta": [1.0, 2.0] }]);
+		value["plot"]["tiles"] = json!({});
+		value
+	}
+
+	// Every serialized field has a schema property and every schema property is a field, so the
+	// schema-driven `--set` and sweep detection see exactly what the types accept.
+	fn assert_covers(value: &Value, schema: &Value, root: &Value, path: &str) {
+		let schema = object_schema(schema, root);
+		match value {
+			Value::Object(map) => {
+				let properties = schema["properties"].as_object().unwrap_or_else(|| panic!("{path} has no properties"));
+				for key in map.keys() {
+					assert!(properties.contains_key(key), "{path}.{key} is missing from the schema");
+				}
+				for key in properties.keys() {
+					assert!(map.contains_key(key), "schema property {path}.{key} is not a field");
+				}
+				for (key, child) in map {
+					assert_covers(child, &properties[key], root, &format!("{path}.{key}"));
+				}
+			}
+			Value::Array(items) => {
+				if let Some(item) = schema.get("items") {
+					for child in items {
+						assert_covers(child, item, root, &format!("{path}[]"));
+					}
+				}
+			}
+			_ => {}
+		}
+	}
+
+	#[test]
+	fn plot_view_fields_match_schema() {
+		let view: PlotView = serde_json::from_value(nested_spec_value()["plot"].clone()).unwrap();
+		let root = base_schema();
+		assert_covers(&serde_json::to_value(view).unwrap(), &plot_view(), &root, "plot");
+	}
+
+	#[test]
+	fn run_spec_fields_match_schema() {
+		let spec: RunSpec = serde_json::from_value(nested_spec_value()).unwrap();
+		let root = base_schema();
+		assert_covers(&serde_json::to_value(spec).unwrap(), &root, &root, "");
+	}
+
+	#[test]
+	fn unknown_fields_are_rejected() {
+		for path in ["plot.no_such_field", "integrator.no_such_field", "no_such_field"] {
+			let mut value = spec_value();
+			crate::settings::set_path(&mut value, path, json!(1));
+			assert!(serde_json::from_value::<RunSpec>(value).is_err(), "{path} was accepted");
+		}
+		let mut value = nested_spec_value();
+		value["plot"]["regions"][0]["extra"] = json!(1);
+		assert!(serde_json::from_value::<RunSpec>(value).is_err());
+	}
+
+	#[test]
+	fn optional_sections_are_walked() {
+		assert_eq!(property_schema("plot.tiles.levels").unwrap()["type"], "integer");
+		assert!(property_schema("plot.tiles.no_such_field").is_none());
+		let schema = run_spec_schema();
+		assert_eq!(schema["$defs"]["TilePyramid"]["properties"]["tile_px"]["anyOf"][1]["$ref"], "#/$defs/Sweep");
+	}
+}
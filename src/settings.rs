@@ -1,12 +1,117 @@
+use crate::analysis::colouring_periods;
+use crate::batch::SweepValue;
+use crate::colour::parse_colour;
+use crate::diagnostics::{has_errors, Diagnostic};
+use crate::error::{Error, Result};
+use crate::integrator::derive_dt_and_k;
+use crate::schema::{property_schema, schema_allows};
 use crate::types::*;
-use serde_json::Value;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
 use std::fs;
 use std::path::Path;
 
-pub fn load_run_spec(path: &str) -> RunSpec {
-	let contents = fs::read_to_string(Path::new(path)).expect("failed to read run spec file");
-	let mut value: Value = serde_json::from_str(&contents).expect("failed to parse run spec json");
+pub(crate) const MIN_SIDE_PX: u32 = 200;
+// With `aspect: equal` the canvas is at most this many times as high as it is wide.
+pub(crate) const MAX_EQUAL_ASPECT: u32 = 8;
+pub(crate) const MIN_TITLE_FONT_PX: u32 = 8;
+pub(crate) const MIN_AXIS_LABEL_FONT_PX: u32 = 8;
+pub(crate) const MIN_TICK_FONT_PX: u32 = 6;
+pub(crate) const DEFAULT_TITLE_FONT_PX: u32 = 64;
+pub(crate) const DEFAULT_AXIS_LABEL_FONT_PX: u32 = 36;
+pub(crate) const DEFAULT_TICK_FONT_PX: u32 = 28;
+pub(crate) const DEFAULT_RTOL: f64 = 1e-8;
+pub(crate) const DEFAULT_ATOL: f64 = 1e-10;
+const AUTO_MARKER_RULE: &str = "automatic: 2 px radius from 1000 px canvases up, otherwise 1 px";
+const BITMAP_WARN_BYTES: u64 = 256 * 1024 * 1024;
+pub(crate) const MAX_TILE_LEVELS: u32 = 14;
+pub(crate) const MIN_TILE_PX: u32 = 64;
+pub(crate) const MAX_TILE_PX: u32 = 2048;
+const PNG_STRIP_BYTES: u64 = 64 * 1024 * 1024;
+const AUTO_STRIP_RULE: &str = "automatic: bands of at most 64 MiB of canvas";
+
+pub struct LoadedSpec {
+	pub spec: RunSpec,
+	pub diagnostics: Vec<Diagnostic>,
+	pub defaulted: Vec<DefaultedValue>,
+	pub sweep: Vec<SweepValue>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DefaultedValue {
+	pub path: String,
+	pub rule: String,
+}
+
+#[derive(Default)]
+struct LoadReport {
+	diagnostics: Vec<Diagnostic>,
+	defaulted: Vec<DefaultedValue>,
+}
+
+impl LoadReport {
+	fn warn(&mut self, path: &str, message: impl Into<String>) {
+		self.diagnostics.push(Diagnostic::warning(path, message));
+	}
+
+	fn defaulted(&mut self, path: &str, rule: impl Into<String>) {
+		self.defaulted.push(DefaultedValue { path: path.to_string(), rule: rule.into() });
+	}
+}
+
+pub fn load_run_spec(path: &str) -> Result<RunSpec> {
+	load_run_spec_with_diagnostics(path).map(|loaded| loaded.spec)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpecFormat {
+	Json,
+	Toml,
+	Yaml,
+}
+
+impl SpecFormat {
+	pub fn from_path(path: &Path) -> SpecFormat {
+		let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+		match ext.as_str() {
+			"toml" => SpecFormat::Toml,
+			"yaml" | "yml" => SpecFormat::Yaml,
+			_ => SpecFormat::Json,
+		}
+	}
+}
+
+pub fn load_run_spec_with_diagnostics(path: &str) -> Result<LoadedSpec> {
+	let value = load_spec_value(path)?;
+	resolve_run_spec(value, path)
+}
+
+pub fn load_spec_value(path: &str) -> Result<Value> {
+	let contents = fs::read_to_string(Path::new(path)).map_err(|e| Error::io(path, e))?;
+	parse_spec_value(&contents, SpecFormat::from_path(Path::new(path))).map_err(|message| Error::parse(path, message))
+}
+
+pub fn parse_spec_value(contents: &str, format: SpecFormat) -> std::result::Result<Value, String> {
+	match format {
+		SpecFormat::Json => serde_json::from_str(contents).map_err(|e| e.to_string()),
+		SpecFormat::Toml => toml::from_str(contents).map_err(|e| e.to_string()),
+		SpecFormat::Yaml => serde_yaml::from_str(contents).map_err(|e| e.to_string()),
+	}
+}
+
+pub fn resolve_run_spec(mut value: Value, path: &str) -> Result<LoadedSpec> {
+	let mut report = LoadReport::default();
 	if let Value::Object(ref mut map) = value {
+		let wrap_given = map
+			.get("poincare")
+			.and_then(|p| p.get("wrap_to_pi"))
+			.is_some_and(|v| !v.is_null());
+		if !wrap_given {
+			report.defaulted("poincare.wrap_to_pi", "true");
+		}
+		if map.get("output").and_then(|o| o.get("data")).is_none() {
+			report.defaulted("output.data", "[\"csv\"]");
+		}
 		let entry = map
 			.entry("poincare".to_string())
 			.or_insert_with(|| Value::Object(serde_json::Map::new()));
@@ -23,62 +128,450 @@ pub fn load_run_spec(path: &str) -> RunSpec {
 			}
 		}
 	} else {
-		panic!("run spec root must be a JSON object");
+		return Err(Error::parse(path, "run spec root must be a table/object"));
 	}
-	let mut spec: RunSpec = serde_json::from_value(value).expect("failed to deserialize run spec");
-	if let Some(size) = spec.plot.marker_size {
-		if size < 1 {
+	let mut spec: RunSpec = serde_json::from_value(value).map_err(|e| Error::parse(path, e))?;
+	check_ignored_settings(&spec, &mut report);
+	match spec.plot.marker_size {
+		Some(0) => {
+			report.warn("plot.marker_size", "0 is not a valid size; using automatic sizing");
+			report.defaulted("plot.marker_size", AUTO_MARKER_RULE);
 			spec.plot.marker_size = None;
 		}
+		None => report.defaulted("plot.marker_size", AUTO_MARKER_RULE),
+		Some(_) => {}
 	}
-	if spec.plot.title_font_px.unwrap_or(0) < 8 {
-		spec.plot.title_font_px = Some(64);
+	match spec.plot.strip_rows {
+		Some(0) => {
+			report.warn("plot.strip_rows", "0 is not a valid strip height; using automatic strips");
+			report.defaulted("plot.strip_rows", AUTO_STRIP_RULE);
+			spec.plot.strip_rows = None;
+		}
+		None => report.defaulted("plot.strip_rows", AUTO_STRIP_RULE),
+		Some(_) => {}
 	}
-	if spec.plot.axis_label_font_px.unwrap_or(0) < 8 {
-		spec.plot.axis_label_font_px = Some(36);
+	raise_font(&mut spec.plot.title_font_px, "plot.title_font_px", MIN_TITLE_FONT_PX, DEFAULT_TITLE_FONT_PX, &mut report);
+	raise_font(&mut spec.plot.axis_label_font_px, "plot.axis_label_font_px", MIN_AXIS_LABEL_FONT_PX, DEFAULT_AXIS_LABEL_FONT_PX, &mut report);
+	raise_font(&mut spec.plot.tick_font_px, "plot.tick_font_px", MIN_TICK_FONT_PX, DEFAULT_TICK_FONT_PX, &mut report);
+	let period = drive_period(spec.phys.omega_d);
+	default_positive(&mut spec.integrator.rtol, "integrator.rtol", DEFAULT_RTOL, "1e-8", &mut report);
+	default_positive(&mut spec.integrator.atol, "integrator.atol", DEFAULT_ATOL, "1e-10", &mut report);
+	default_positive(&mut spec.integrator.dt_init, "integrator.dt_init", period / 400.0, "drive period / 400", &mut report);
+	default_positive(&mut spec.integrator.dt_min, "integrator.dt_min", period / 20000.0, "drive period / 20000", &mut report);
+	default_positive(&mut spec.integrator.dt_max, "integrator.dt_max", period / 20.0, "drive period / 20", &mut report);
+	if spec.output.out_base.trim().is_empty() {
+		report.warn("output.out_base", "empty; using \"poincare\"");
+		report.defaulted("output.out_base", "\"poincare\"");
 	}
-	if spec.plot.tick_font_px.unwrap_or(0) < 6 {
-		spec.plot.tick_font_px = Some(28);
+	derive_outputs(&mut spec);
+	let LoadReport { mut diagnostics, defaulted } = report;
+	diagnostics.extend(validate_run_spec(&spec));
+	if has_errors(&diagnostics) {
+		diagnostics.sort_by_key(|d| std::cmp::Reverse(d.severity));
+		return Err(Error::InvalidSpec(diagnostics));
 	}
+	Ok(LoadedSpec { spec, diagnostics, defaulted, sweep: Vec::new() })
+}
+
+fn check_ignored_settings(spec: &RunSpec, report: &mut LoadReport) {
+	let integ = &spec.integrator;
+	let method = integ.method;
+	if is_adaptive(method) {
+		if integ.dt_user.is_some() {
+			report.warn("integrator.dt_user", format!("ignored for {method:?}"));
+		}
+	} else {
+		let adaptive_only = [
+			("integrator.rtol", integ.rtol),
+			("integrator.atol", integ.atol),
+			("integrator.dt_init", integ.dt_init),
+			("integrator.dt_min", integ.dt_min),
+			("integrator.dt_max", integ.dt_max),
+		];
+		for (path, value) in adaptive_only {
+			if value.is_some() {
+				report.warn(path, format!("ignored for {method:?}"));
+			}
+		}
+	}
+}
+
+fn raise_font(size: &mut Option<u32>, path: &str, min: u32, default: u32, report: &mut LoadReport) {
+	match *size {
+		Some(px) if px >= min => return,
+		Some(px) => report.warn(path, format!("{px} is below the minimum of {min}; using {default}")),
+		None => {}
+	}
+	report.defaulted(path, default.to_string());
+	*size = Some(default);
+}
+
+fn default_positive(value: &mut Option<f64>, path: &str, default: f64, rule: &str, report: &mut LoadReport) {
+	match *value {
+		Some(v) if v > 0.0 => return,
+		Some(v) => report.warn(path, format!("{v} is not positive; using {default:e}")),
+		None => {}
+	}
+	report.defaulted(path, rule);
+	*value = Some(default);
+}
+
+pub fn apply_override(value: &mut Value, assignment: &str) -> Result<()> {
+	let invalid = |path: &str, message: &str| Error::InvalidSpec(vec![Diagnostic::error(path, message)]);
+	let (path, raw) = assignment
+		.split_once('=')
+		.ok_or_else(|| invalid(assignment, "override must have the form key.path=value"))?;
+	let path = path.trim();
+	let schema = property_schema(path).ok_or_else(|| invalid(path, "no such field in the run spec"))?;
+	let names_variant = schema
+		.get("enum")
+		.and_then(Value::as_array)
+		.is_some_and(|variants| variants.iter().any(Value::is_string));
+	let new_value = if schema_allows(&schema, "string") {
+		Value::String(raw.to_string())
+	} else {
+		match serde_json::from_str(raw) {
+			Ok(parsed) => parsed,
+			// Lets `--set plot.render=density` name an enum variant without shell-escaped quotes.
+			Err(_) if names_variant => Value::String(raw.to_string()),
+			Err(e) => return Err(invalid(path, &format!("{raw:?} is not a valid JSON value: {e}"))),
+		}
+	};
+	set_path(value, path, new_value);
+	Ok(())
+}
+
+pub(crate) fn set_path(value: &mut Value, path: &str, new_value: Value) {
+	let mut current = value;
+	for key in path.split('.') {
+		if !current.is_object() {
+			*current = Value::Object(Map::new());
+		}
+		current = current
+			.as_object_mut()
+			.expect("just ensured an object")
+			.entry(key.to_string())
+			.or_insert(Value::Null);
+	}
+	*current = new_value;
+}
+
+pub fn explain(loaded: &LoadedSpec) -> Value {
+	let spec = &loaded.spec;
 	let period = drive_period(spec.phys.omega_d);
-	if spec.integrator.rtol.unwrap_or(0.0) <= 0.0 {
-		spec.integrator.rtol = Some(1e-8);
+	let total_periods = spec.integrator.n_periods_warmup + spec.integrator.n_periods_samples;
+	let stepping = if is_adaptive(spec.integrator.method) {
+		let dt_min = spec.integrator.dt_min.unwrap_or(period / 20000.0);
+		let dt_max = spec.integrator.dt_max.unwrap_or(period / 20.0);
+		let dt_init = spec.integrator.dt_init.unwrap_or(period / 400.0).clamp(dt_min, dt_max);
+		json!({
+			"kind": "adaptive",
+			"rtol": spec.integrator.rtol,
+			"atol": spec.integrator.atol,
+			"dt_init": dt_init,
+			"dt_min": dt_min,
+			"dt_max": dt_max,
+		})
+	} else {
+		let (dt, k) = derive_dt_and_k(&spec.phys, &spec.integrator);
+		let source = match spec.integrator.dt_user {
+			Some(dt_user) if dt_user > 0.0 => "integrator.dt_user snapped to an even number of steps per period",
+			_ => "heuristic from drive period, damping and drive amplitude",
+		};
+		json!({
+			"kind": "fixed",
+			"dt": dt,
+			"steps_per_period": k,
+			"total_steps": total_periods.saturating_mul(k),
+			"source": source,
+		})
+	};
+	let defaulted: Map<String, Value> = loaded
+		.defaulted
+		.iter()
+		.map(|d| (d.path.clone(), Value::String(d.rule.clone())))
+		.collect();
+	json!({
+		"spec": spec,
+		"sweep": loaded.sweep,
+		"derived": {
+			"drive_period": period,
+			"total_periods": total_periods,
+			"stepping": stepping,
+		},
+		"defaulted": defaulted,
+		"diagnostics": loaded.diagnostics,
+	})
+}
+
+pub fn validate_run_spec(spec: &RunSpec) -> Vec<Diagnostic> {
+	let mut diagnostics = Vec::new();
+	validate_phys(&spec.phys, &mut diagnostics);
+	validate_init(&spec.init, &mut diagnostics);
+	validate_integrator(spec, &mut diagnostics);
+	validate_plot(spec, &mut diagnostics);
+	if spec.output.out_base.trim().is_empty() {
+		diagnostics.push(Diagnostic::error("output.out_base", "must not be empty"));
+	}
+	for (i, format) in spec.output.data.iter().enumerate() {
+		if spec.output.data[..i].contains(format) {
+			diagnostics.push(Diagnostic::warning(&format!("output.data[{i}]"), format!("{} is listed more than once", format.extension())));
+		}
+	}
+	diagnostics
+}
+
+fn validate_phys(phys: &PhysicalParams, diagnostics: &mut Vec<Diagnostic>) {
+	require(phys.g > 0.0, "phys.g", format!("must be > 0 (got {})", phys.g), diagnostics);
+	require(phys.l > 0.0, "phys.l", format!("must be > 0 (got {})", phys.l), diagnostics);
+	require(phys.q >= 0.0, "phys.q", format!("must be >= 0 (got {})", phys.q), diagnostics);
+	require(phys.f_drive.is_finite(), "phys.f_drive", format!("must be finite (got {})", phys.f_drive), diagnostics);
+	require(phys.omega_d > 0.0, "phys.omega_d", format!("must be > 0 (got {})", phys.omega_d), diagnostics);
+	for (path, value) in [("phys.g", phys.g), ("phys.l", phys.l), ("phys.q", phys.q), ("phys.omega_d", phys.omega_d)] {
+		if value.is_infinite() {
+			diagnostics.push(Diagnostic::error(path, "must be finite"));
+		}
+	}
+}
+
+fn validate_init(init: &InitialState, diagnostics: &mut Vec<Diagnostic>) {
+	for (path, value) in [("init.theta0", init.theta0), ("init.omega0", init.omega0), ("init.t0", init.t0)] {
+		require(value.is_finite(), path, format!("must be finite (got {value})"), diagnostics);
+	}
+}
+
+fn validate_integrator(spec: &RunSpec, diagnostics: &mut Vec<Diagnostic>) {
+	let integ = &spec.integrator;
+	require(integ.n_periods_samples > 0, "integrator.n_periods_samples", "must be > 0", diagnostics);
+	if !spec.phys.omega_d.is_finite() || spec.phys.omega_d <= 0.0 {
+		return;
+	}
+	if is_adaptive(integ.method) {
+		for (path, value) in [("integrator.rtol", integ.rtol), ("integrator.atol", integ.atol)] {
+			match value {
+				Some(v) if v <= 0.0 => diagnostics.push(Diagnostic::error(path, format!("must be > 0 (got {v})"))),
+				Some(v) if v < 1e-14 => diagnostics.push(Diagnostic::warning(
+					path,
+					format!("{v:e} is below f64 resolution; steps will be pinned at integrator.dt_min"),
+				)),
+				_ => {}
+			}
+		}
+		if let (Some(dt_min), Some(dt_max)) = (integ.dt_min, integ.dt_max) {
+			if dt_min > dt_max {
+				diagnostics.push(Diagnostic::error(
+					"integrator.dt_min",
+					format!("{dt_min:e} exceeds integrator.dt_max ({dt_max:e})"),
+				));
+			} else if let Some(dt_init) = integ.dt_init {
+				if dt_init < dt_min || dt_init > dt_max {
+					diagnostics.push(Diagnostic::warning(
+						"integrator.dt_init",
+						format!("{dt_init:e} lies outside [dt_min, dt_max] and will be clamped"),
+					));
+				}
+			}
+		}
+	} else if let Some(dt_user) = integ.dt_user {
+		if dt_user <= 0.0 {
+			diagnostics.push(Diagnostic::warning(
+				"integrator.dt_user",
+				format!("{dt_user} is not positive; a step is chosen automatically"),
+			));
+		} else {
+			let (dt, k) = derive_dt_and_k(&spec.phys, integ);
+			if ((dt - dt_user) / dt_user).abs() > 1e-9 {
+				diagnostics.push(Diagnostic::warning(
+					"integrator.dt_user",
+					format!("adjusted from {dt_user:e} to {dt:e} ({k} steps per drive period)"),
+				));
+			}
+		}
+	}
+}
+
+// The canvas height, or the most it can be when the plotted ranges set it.
+pub(crate) fn max_canvas_height(plot: &PlotView) -> u32 {
+	plot.canvas_height().unwrap_or(plot.canvas_width().saturating_mul(MAX_EQUAL_ASPECT))
+}
+
+// Rows of the PNG rendered per strip; strips never exceed the canvas.
+pub(crate) fn png_strip_rows(plot: &PlotView) -> u32 {
+	let width = plot.canvas_width().max(1) as u64;
+	let height = max_canvas_height(plot).max(1) as u64;
+	let rows = plot.strip_rows.map_or(PNG_STRIP_BYTES / (width * 3), u64::from);
+	rows.clamp(1, height) as u32
+}
+
+fn validate_plot(spec: &RunSpec, diagnostics: &mut Vec<Diagnostic>) {
+	let plot = &spec.plot;
+	let sides = [("plot.side_px", Some(plot.side_px)), ("plot.width_px", plot.width_px), ("plot.height_px", plot.height_px)];
+	let mut sides_ok = true;
+	for (path, side) in sides {
+		if let Some(px) = side {
+			sides_ok &= px >= MIN_SIDE_PX;
+			require(px >= MIN_SIDE_PX, path, format!("must be at least {MIN_SIDE_PX} (got {px})"), diagnostics);
+		}
 	}
-	if spec.integrator.atol.unwrap_or(0.0) <= 0.0 {
-		spec.integrator.atol = Some(1e-10);
+	if let Some(ratio) = plot.aspect_ratio {
+		if !(ratio.is_finite() && ratio > 0.0) {
+			sides_ok = false;
+			diagnostics.push(Diagnostic::error("plot.aspect_ratio", format!("must be a positive number (got {ratio})")));
+		} else if plot.aspect == Aspect::Equal {
+			diagnostics.push(Diagnostic::warning("plot.aspect_ratio", "ignored when plot.aspect is \"equal\""));
+		} else if let Some(height) = plot.canvas_height().filter(|&h| h < MIN_SIDE_PX) {
+			sides_ok = false;
+			diagnostics.push(Diagnostic::error(
+				"plot.aspect_ratio",
+				format!("makes the canvas {height} pixels high, below the minimum of {MIN_SIDE_PX}"),
+			));
+		}
 	}
-	if spec.integrator.dt_init.unwrap_or(0.0) <= 0.0 {
-		spec.integrator.dt_init = Some(period / 400.0);
+	if plot.aspect == Aspect::Equal {
+		// Ranges taken from the data are only known once the run is done; the cap applies to those too.
+		let [x_min, x_max] = plot.x_range.unwrap_or([-4.0, 4.0]);
+		let main = ("plot.y_range".to_string(), x_max - x_min, plot.y_range);
+		let regions = plot.regions.iter().enumerate().map(|(i, r)| (format!("plot.regions[{i}].omega"), r.theta[1] - r.theta[0], r.omega));
+		for (path, x_span, y_range) in std::iter::once(main).chain(regions) {
+			let Some([y_min, y_max]) = y_range else { continue };
+			let ratio = (y_max - y_min) / x_span;
+			if ratio > MAX_EQUAL_ASPECT as f64 {
+				let message = format!("makes the plot area {ratio:.0} times as high as wide; the canvas stops at {MAX_EQUAL_ASPECT} times its width");
+				diagnostics.push(Diagnostic::warning(&path, message));
+			}
+		}
 	}
-	if spec.integrator.dt_min.unwrap_or(0.0) <= 0.0 {
-		spec.integrator.dt_min = Some(period / 20000.0);
+	if plot.height_px.is_some() && (plot.aspect == Aspect::Equal || plot.aspect_ratio.is_some()) {
+		diagnostics.push(Diagnostic::warning("plot.height_px", "ignored when plot.aspect or plot.aspect_ratio sets the height"));
 	}
-	if spec.integrator.dt_max.unwrap_or(0.0) <= 0.0 {
-		spec.integrator.dt_max = Some(period / 20.0);
+	if let Some(dpi) = plot.dpi {
+		require(dpi.is_finite() && dpi > 0.0, "plot.dpi", format!("must be a positive number (got {dpi})"), diagnostics);
+	}
+	if sides_ok {
+		// One RGB strip plus the point layer rasterised for it before it is blitted onto the strip.
+		let width = plot.canvas_width();
+		let rows = png_strip_rows(plot);
+		let bytes = width as u64 * rows as u64 * 7;
+		if bytes >= BITMAP_WARN_BYTES {
+			diagnostics.push(Diagnostic::warning(
+				"plot.strip_rows",
+				format!("{width}x{rows} PNG strips need about {} MiB of memory", bytes / (1024 * 1024)),
+			));
+		}
+	}
+	let fonts = [
+		("plot.title_font_px", plot.title_font_px, MIN_TITLE_FONT_PX),
+		("plot.axis_label_font_px", plot.axis_label_font_px, MIN_AXIS_LABEL_FONT_PX),
+		("plot.tick_font_px", plot.tick_font_px, MIN_TICK_FONT_PX),
+	];
+	for (path, size, min) in fonts {
+		if let Some(px) = size {
+			require(px >= min, path, format!("must be at least {min} (got {px})"), diagnostics);
+		}
+	}
+	let ranges = [("plot.x_range", plot.x_range), ("plot.y_range", plot.y_range)];
+	for (path, range) in ranges {
+		if let Some(range) = range {
+			require_range(range, path, diagnostics);
+		}
+	}
+	let steps = [("plot.x_tick_step", plot.x_tick_step), ("plot.y_tick_step", plot.y_tick_step)];
+	for (path, step) in steps {
+		if let Some(step) = step {
+			require(step.is_finite() && step > 0.0, path, format!("must be a positive number (got {step})"), diagnostics);
+		}
+	}
+	if plot.colour_by != ColourBy::None && plot.render == RenderMode::Density {
+		diagnostics.push(Diagnostic::warning("plot.colour_by", "ignored when plot.render is \"density\""));
+	}
+	let extra = colouring_periods(plot, spec.integrator.n_periods_samples);
+	if extra > 0 {
+		diagnostics.push(Diagnostic::warning(
+			"plot.colour_by",
+			format!("\"ftle\" integrates {extra} more drive periods after sampling to colour the points"),
+		));
+	}
+	if plot.html_point_budget == Some(0) {
+		diagnostics.push(Diagnostic::error("plot.html_point_budget", "must be at least 1"));
+	}
+	if let Some(tiles) = &plot.tiles {
+		let (levels, px) = (tiles.levels, tiles.tile_px);
+		require(
+			(1..=MAX_TILE_LEVELS).contains(&levels),
+			"plot.tiles.levels",
+			format!("must be between 1 and {MAX_TILE_LEVELS} (got {levels})"),
+			diagnostics,
+		);
+		require(
+			(MIN_TILE_PX..=MAX_TILE_PX).contains(&px),
+			"plot.tiles.tile_px",
+			format!("must be between {MIN_TILE_PX} and {MAX_TILE_PX} (got {px})"),
+			diagnostics,
+		);
+	}
+	let series_colours = plot.series.iter().enumerate().map(|(i, style)| (format!("plot.series[{i}].colour"), &style.colour));
+	let theme_colours = [("plot.background", &plot.background), ("plot.foreground", &plot.foreground), ("plot.marker_colour", &plot.marker_colour)]
+		.map(|(path, colour)| (path.to_string(), colour));
+	for (path, colour) in theme_colours.into_iter().chain(series_colours) {
+		if let Some(colour) = colour {
+			require(parse_colour(colour).is_some(), &path, format!("must be a colour like \"#1f77b4\" (got {colour:?})"), diagnostics);
+		}
+	}
+	if let Some(opacity) = plot.marker_opacity {
+		require((0.0..=1.0).contains(&opacity), "plot.marker_opacity", format!("must be between 0 and 1 (got {opacity})"), diagnostics);
+	}
+	if plot.frame_width == Some(0) {
+		diagnostics.push(Diagnostic::error("plot.frame_width", "must be at least 1"));
+	}
+	if plot.font_family.as_deref().is_some_and(|family| family.trim().is_empty()) {
+		diagnostics.push(Diagnostic::error("plot.font_family", "must not be empty"));
+	}
+	let mut names = Vec::with_capacity(plot.regions.len());
+	for (i, region) in plot.regions.iter().enumerate() {
+		validate_region(region, &format!("plot.regions[{i}]"), &names, diagnostics);
+		names.push(region.name.as_str());
 	}
-	derive_outputs(&mut spec);
-	validate_run_spec(&spec);
-	spec
 }
 
-pub fn validate_run_spec(spec: &RunSpec) {
-	assert!(spec.phys.g > 0.0, "gravity must be positive");
-	assert!(spec.phys.l > 0.0, "pendulum length must be positive");
-	assert!(spec.phys.q >= 0.0, "damping must be non-negative");
-	assert!(spec.phys.omega_d > 0.0, "drive frequency must be positive");
-	assert!(spec.integrator.n_periods_warmup >= 0, "warmup periods must be non-negative");
-	assert!(spec.integrator.n_periods_samples > 0, "sample periods must be positive");
-	assert!(spec.plot.side_px >= 200, "plot side length must be at least 200");
-	if let Some(size) = spec.plot.title_font_px {
-		assert!(size >= 8, "title font size must be at least 8");
+fn validate_region(region: &Region, path: &str, earlier: &[&str], diagnostics: &mut Vec<Diagnostic>) {
+	let name_ok = !region.name.is_empty()
+		&& region.name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '='));
+	require(
+		name_ok,
+		&format!("{path}.name"),
+		format!("{:?} must be non-empty and use only letters, digits, '_', '-', '.' or '='", region.name),
+		diagnostics,
+	);
+	if earlier.contains(&region.name.as_str()) {
+		diagnostics.push(Diagnostic::error(&format!("{path}.name"), format!("{:?} is used by an earlier region", region.name)));
 	}
-	if let Some(size) = spec.plot.axis_label_font_px {
-		assert!(size >= 8, "axis label font size must be at least 8");
+	let mut bounds = vec![("theta", region.theta)];
+	bounds.extend(region.omega.map(|omega| ("omega", omega)));
+	for (axis, range) in bounds {
+		require_range(range, &format!("{path}.{axis}"), diagnostics);
 	}
-	if let Some(size) = spec.plot.tick_font_px {
-		assert!(size >= 6, "tick font size must be at least 6");
+	if let Some(side_px) = region.side_px {
+		require(side_px >= MIN_SIDE_PX, &format!("{path}.side_px"), format!("must be at least {MIN_SIDE_PX} (got {side_px})"), diagnostics);
 	}
-	assert!(!spec.output.out_base.trim().is_empty(), "output base cannot be empty");
+}
+
+fn require_range([min, max]: [f64; 2], path: &str, diagnostics: &mut Vec<Diagnostic>) {
+	require(
+		min.is_finite() && max.is_finite() && min < max,
+		path,
+		format!("expected finite [min, max] with min < max (got [{min}, {max}])"),
+		diagnostics,
+	);
+}
+
+fn require(condition: bool, path: &str, message: impl Into<String>, diagnostics: &mut Vec<Diagnostic>) {
+	if !condition {
+		diagnostics.push(Diagnostic::error(path, message));
+	}
+}
+
+fn is_adaptive(method: IntegratorMethod) -> bool {
+	matches!(method, IntegratorMethod::RK45 | IntegratorMethod::BulirschStoer)
 }
 
 pub fn derive_outputs(spec: &mut RunSpec) {
@@ -90,3 +583,74 @@ pub fn derive_outputs(spec: &mut RunSpec) {
 pub fn drive_period(omega_d: f64) -> f64 {
 	2.0 * std::f64::consts::PI / omega_d
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn overridden(assignments: &[&str]) -> Result<Value> {
+		let mut value = json!({ "plot": { "side_px": 800, "title": "t" } });
+		for assignment in assignments {
+			apply_override(&mut value, assignment)?;
+		}
+		Ok(value)
+	}
+
+	#[test]
+	fn override_parses_json_values() {
+		let value = overridden(&["plot.side_px=1200", "plot.x_range=[0, 3.2]", "phys.f_drive=1.35"]).unwrap();
+		assert_eq!(value["plot"]["side_px"], json!(1200));
+		assert_eq!(value["plot"]["x_range"], json!([0, 3.2]));
+		assert_eq!(value["phys"]["f_drive"], json!(1.35));
+	}
+
+	#[test]
+	fn override_keeps_strings_verbatim() {
+		let value = overridden(&["plot.title=F_D = 1.35", "output.out_base=123"]).unwrap();
+		assert_eq!(value["plot"]["title"], json!("F_D = 1.35"));
+		assert_eq!(value["output"]["out_base"], json!("123"));
+	}
+
+	#[test]
+	fn override_accepts_bare_enum_variants() {
+		let value = overridden(&["plot.render=density", "integrator.method=RK45", "plot.legend=\"none\""]).unwrap();
+		assert_eq!(value["plot"]["render"], json!("density"));
+		assert_eq!(value["integrator"]["method"], json!("RK45"));
+		assert_eq!(value["plot"]["legend"], json!("none"));
+	}
+
+	#[test]
+	fn override_rejects_bad_values_and_paths() {
+		for assignment in ["plot.side_px=1e3x", "plot.x_range=[0,3", "plot.no_such_field=1", "plot.side_px"] {
+			assert!(matches!(overridden(&[assignment]), Err(Error::InvalidSpec(_))), "{assignment} was accepted");
+		}
+	}
+
+	#[test]
+	fn override_reaches_optional_sections() {
+		let value = overridden(&["plot.tiles.levels=3"]).unwrap();
+		assert_eq!(value["plot"]["tiles"], json!({ "levels": 3 }));
+		let mut spec = crate::test_support::spec_value();
+		apply_override(&mut spec, "plot.tiles.tile_px=128").unwrap();
+		let spec: RunSpec = serde_json::from_value(spec).unwrap();
+		assert_eq!(spec.plot.tiles.map(|t| (t.levels, t.tile_px)), Some((6, 128)));
+		assert!(overridden(&["plot.tiles.no_such_field=1"]).is_err());
+	}
+
+	#[test]
+	fn equal_aspect_height_is_bounded() {
+		let mut spec = crate::test_support::spec();
+		spec.plot.aspect = Aspect::Equal;
+		spec.plot.strip_rows = Some(u32::MAX);
+		assert_eq!(max_canvas_height(&spec.plot), 800 * MAX_EQUAL_ASPECT);
+		assert_eq!(png_strip_rows(&spec.plot), 800 * MAX_EQUAL_ASPECT);
+		let warned = |spec: &RunSpec| validate_run_spec(spec).iter().any(|d| d.to_string().contains("times as high as wide"));
+		spec.plot.y_range = Some([-30.0, 30.0]);
+		assert!(!warned(&spec));
+		spec.plot.y_range = Some([-40.0, 40.0]);
+		assert!(warned(&spec));
+		spec.plot.y_range = None;
+		spec.plot.regions = vec![Region { name: "z".to_string(), theta: [0.0, 0.1], omega: Some([0.0, 1.0]), title: None, side_px: None }];
+		assert!(warned(&spec));
+	}
+}
@@ -1,7 +1,5 @@
 use crate::integrator::{
 	derive_dt_and_k,
-	steps_for_sampling,
-	steps_for_warmup,
 	AdaptiveStepper,
 	BulirschStoer,
 	EulerCromer,
@@ -9,33 +7,138 @@ use crate::integrator::{
 	RK45,
 	Stepper,
 };
+use crate::error::{Error, Result};
 use crate::sampling::{PoincareSampler, TimeGridSampler};
 use crate::settings::drive_period;
 use crate::types::*;
+use std::time::{Duration, Instant};
 
-pub fn run(spec: &RunSpec) -> Vec<SamplePoint> {
-	match spec.integrator.method {
-		IntegratorMethod::EulerCromer | IntegratorMethod::RK4 => run_fixed(spec),
-		IntegratorMethod::RK45 | IntegratorMethod::BulirschStoer => run_adaptive(spec),
+#[derive(Clone, Copy, Debug)]
+pub struct Progress {
+	pub periods_done: usize,
+	pub periods_total: usize,
+	pub elapsed: Duration,
+	pub eta: Option<Duration>,
+	pub dt: f64,
+}
+
+impl Progress {
+	pub fn fraction(&self) -> f64 {
+		if self.periods_total == 0 {
+			1.0
+		} else {
+			self.periods_done as f64 / self.periods_total as f64
+		}
 	}
 }
 
-fn run_fixed(spec: &RunSpec) -> Vec<SamplePoint> {
-	let mut stepper = build_stepper(spec.integrator.method);
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Control {
+	Continue,
+	Cancel,
+}
+
+pub trait RunObserver {
+	fn on_progress(&mut self, progress: &Progress) -> Control;
+}
+
+impl<F: FnMut(&Progress) -> Control> RunObserver for F {
+	fn on_progress(&mut self, progress: &Progress) -> Control {
+		self(progress)
+	}
+}
+
+pub struct NoObserver;
+
+impl RunObserver for NoObserver {
+	fn on_progress(&mut self, _progress: &Progress) -> Control {
+		Control::Continue
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct RunOutcome {
+	pub samples: Vec<SamplePoint>,
+	pub cancelled: bool,
+	pub stats: IntegratorStats,
+}
+
+pub(crate) struct Monitor<'a> {
+	observer: &'a mut dyn RunObserver,
+	start: Instant,
+	periods_done: usize,
+	periods_total: usize,
+}
+
+impl<'a> Monitor<'a> {
+	pub(crate) fn new(observer: &'a mut dyn RunObserver, periods_total: usize) -> Self {
+		Self { observer, start: Instant::now(), periods_done: 0, periods_total }
+	}
+
+	pub(crate) fn periods_done(&mut self, periods: usize, dt: f64) -> Control {
+		self.periods_done = (self.periods_done + periods).min(self.periods_total);
+		let elapsed = self.start.elapsed();
+		let eta = if self.periods_done == 0 {
+			None
+		} else {
+			let remaining = (self.periods_total - self.periods_done) as f64;
+			Some(elapsed.mul_f64(remaining / self.periods_done as f64))
+		};
+		let progress = Progress {
+			periods_done: self.periods_done,
+			periods_total: self.periods_total,
+			elapsed,
+			eta,
+			dt,
+		};
+		self.observer.on_progress(&progress)
+	}
+}
+
+pub fn run(spec: &RunSpec, observer: &mut dyn RunObserver) -> Result<RunOutcome> {
+	let total = spec.integrator.n_periods_warmup + spec.integrator.n_periods_samples;
+	let start = Instant::now();
+	let mut monitor = Monitor::new(observer, total);
+	let mut outcome = match spec.integrator.method {
+		IntegratorMethod::EulerCromer | IntegratorMethod::RK4 => run_fixed(spec, &mut monitor),
+		IntegratorMethod::RK45 | IntegratorMethod::BulirschStoer => run_adaptive(spec, &mut monitor),
+	}?;
+	outcome.stats.wall_seconds = start.elapsed().as_secs_f64();
+	Ok(outcome)
+}
+
+fn fixed_stats(periods: usize, k: usize, dt: f64) -> IntegratorStats {
+	IntegratorStats { steps_accepted: (periods * k) as u64, dt_smallest: dt, dt_largest: dt, ..IntegratorStats::default() }
+}
+
+fn run_fixed(spec: &RunSpec, monitor: &mut Monitor) -> Result<RunOutcome> {
+	let mut stepper = build_stepper(spec.integrator.method)?;
 	let (dt, k) = derive_dt_and_k(&spec.phys, &spec.integrator);
-	let warmup_steps = steps_for_warmup(&spec.integrator, k);
-	let sample_steps = steps_for_sampling(&spec.integrator, k);
-	let state = State { t: spec.init.t0, theta: spec.init.theta0, omega: spec.init.omega0 };
-	let state = integrate_warmup(stepper.as_mut(), state, &spec.phys, dt, warmup_steps);
-	let mut sampler = PoincareSampler::new(k, spec.poincare.wrap_to_pi);
-	integrate_and_sample(stepper.as_mut(), state, &spec.phys, dt, sample_steps, &mut sampler)
+	let mut state = State { t: spec.init.t0, theta: spec.init.theta0, omega: spec.init.omega0 };
+	let mut points = Vec::with_capacity(spec.integrator.n_periods_samples);
+	for i in 0..spec.integrator.n_periods_warmup {
+		state = integrate_warmup(stepper.as_mut(), state, &spec.phys, dt, k);
+		if monitor.periods_done(1, dt) == Control::Cancel {
+			return Ok(RunOutcome { samples: points, cancelled: true, stats: fixed_stats(i + 1, k, dt) });
+		}
+	}
+	let mut sampler = PoincareSampler::new(k, spec.integrator.n_periods_warmup, spec.poincare.wrap_to_pi);
+	for _ in 0..spec.integrator.n_periods_samples {
+		state = integrate_and_sample(stepper.as_mut(), state, &spec.phys, dt, k, &mut sampler, &mut points);
+		if monitor.periods_done(1, dt) == Control::Cancel {
+			let periods = spec.integrator.n_periods_warmup + points.len();
+			return Ok(RunOutcome { samples: points, cancelled: true, stats: fixed_stats(periods, k, dt) });
+		}
+	}
+	let periods = spec.integrator.n_periods_warmup + spec.integrator.n_periods_samples;
+	Ok(RunOutcome { samples: points, cancelled: false, stats: fixed_stats(periods, k, dt) })
 }
 
-fn run_adaptive(spec: &RunSpec) -> Vec<SamplePoint> {
+fn run_adaptive(spec: &RunSpec, monitor: &mut Monitor) -> Result<RunOutcome> {
 	let period = drive_period(spec.phys.omega_d);
 	let mut state = State { t: spec.init.t0, theta: spec.init.theta0, omega: spec.init.omega0 };
 	let mut sampler = TimeGridSampler::new(state.t, spec.integrator.n_periods_warmup, period, spec.poincare.wrap_to_pi);
-	let mut stepper = build_adaptive(spec.integrator.method);
+	let mut stepper = build_adaptive(spec.integrator.method)?;
 	let mut dt_init = spec.integrator.dt_init.unwrap_or(period / 400.0);
 	let dt_min = spec.integrator.dt_min.unwrap_or(period / 20000.0);
 	let dt_max = spec.integrator.dt_max.unwrap_or(period / 20.0);
@@ -43,6 +146,15 @@ fn run_adaptive(spec: &RunSpec) -> Vec<SamplePoint> {
 	let atol = spec.integrator.atol.unwrap_or(1e-10);
 	let mut points = Vec::with_capacity(spec.integrator.n_periods_samples);
 	dt_init = dt_init.clamp(dt_min, dt_max);
+	for i in 0..spec.integrator.n_periods_warmup {
+		let target = spec.init.t0 + (i + 1) as f64 * period;
+		let (next_state, used_dt) = stepper.advance_to(state, &spec.phys, target, rtol, atol, dt_init, dt_min, dt_max);
+		state = next_state;
+		dt_init = used_dt.clamp(dt_min, dt_max);
+		if monitor.periods_done(1, used_dt) == Control::Cancel {
+			return Ok(RunOutcome { samples: points, cancelled: true, stats: stepper.stats() });
+		}
+	}
 	for _ in 0..spec.integrator.n_periods_samples {
 		let target = sampler.target_time();
 		let (next_state, used_dt) = stepper.advance_to(state, &spec.phys, target, rtol, atol, dt_init, dt_min, dt_max);
@@ -50,23 +162,26 @@ fn run_adaptive(spec: &RunSpec) -> Vec<SamplePoint> {
 		sampler.advance();
 		state = next_state;
 		dt_init = used_dt.clamp(dt_min, dt_max);
+		if monitor.periods_done(1, used_dt) == Control::Cancel {
+			return Ok(RunOutcome { samples: points, cancelled: true, stats: stepper.stats() });
+		}
 	}
-	points
+	Ok(RunOutcome { samples: points, cancelled: false, stats: stepper.stats() })
 }
 
-pub fn build_stepper(method: IntegratorMethod) -> Box<dyn Stepper> {
+pub fn build_stepper(method: IntegratorMethod) -> Result<Box<dyn Stepper>> {
 	match method {
-		IntegratorMethod::EulerCromer => Box::new(EulerCromer),
-		IntegratorMethod::RK4 => Box::new(RK4),
-		_ => panic!("adaptive method requested from fixed-step builder"),
+		IntegratorMethod::EulerCromer => Ok(Box::new(EulerCromer)),
+		IntegratorMethod::RK4 => Ok(Box::new(RK4)),
+		_ => Err(Error::UnsupportedMethod { method, expected: "fixed-step" }),
 	}
 }
 
-pub fn build_adaptive(method: IntegratorMethod) -> Box<dyn AdaptiveStepper> {
+pub fn build_adaptive(method: IntegratorMethod) -> Result<Box<dyn AdaptiveStepper>> {
 	match method {
-		IntegratorMethod::RK45 => Box::new(RK45),
-		IntegratorMethod::BulirschStoer => Box::new(BulirschStoer),
-		_ => panic!("fixed-step method requested from adaptive builder"),
+		IntegratorMethod::RK45 => Ok(Box::new(RK45::default())),
+		IntegratorMethod::BulirschStoer => Ok(Box::new(BulirschStoer::default())),
+		_ => Err(Error::UnsupportedMethod { method, expected: "adaptive" }),
 	}
 }
 
@@ -78,9 +193,15 @@ pub fn integrate_warmup(stepper: &mut dyn Stepper, state: State, phys: &Physical
 	current
 }
 
-pub fn integrate_and_sample(stepper: &mut dyn Stepper, state: State, phys: &PhysicalParams, dt: f64, steps: usize, sampler: &mut PoincareSampler) -> Vec<SamplePoint> {
-	let reserve = if sampler.k == 0 { 0 } else { steps / sampler.k + 1 };
-	let mut points = Vec::with_capacity(reserve);
+pub fn integrate_and_sample(
+	stepper: &mut dyn Stepper,
+	state: State,
+	phys: &PhysicalParams,
+	dt: f64,
+	steps: usize,
+	sampler: &mut PoincareSampler,
+	points: &mut Vec<SamplePoint>,
+) -> State {
 	let mut current = state;
 	for _ in 0..steps {
 		current = stepper.step(current, phys, dt);
@@ -88,5 +209,5 @@ pub fn integrate_and_sample(stepper: &mut dyn Stepper, state: State, phys: &Phys
 			points.push(sampler.on_sample(&current));
 		}
 	}
-	points
+	current
 }
@@ -0,0 +1,36 @@
+use crate::simulate::RunOutcome;
+use crate::types::{IntegratorStats, RunSpec, SamplePoint};
+use serde_json::{json, Value};
+
+pub(crate) fn spec_value() -> Value {
+	json!({
+		"phys": { "g": 9.8, "l": 9.8, "q": 0.5, "f_drive": 1.2, "omega_d": 0.6666666666666666 },
+		"integrator": { "method": "RK4", "n_periods_warmup": 0, "n_periods_samples": 1 },
+		"init": { "theta0": 0.2, "omega0": 0.0, "t0": 0.0 },
+		"poincare": { "wrap_to_pi": true },
+		"plot": { "side_px": 800, "title": "t" },
+		"output": { "out_base": "t" }
+	})
+}
+
+pub(crate) fn spec() -> RunSpec {
+	serde_json::from_value(spec_value()).expect("test spec is valid")
+}
+
+// Samples whose unwrapped θ winds past ±π so that wrapping is exercised.
+pub(crate) fn outcome(samples: usize) -> RunOutcome {
+	let samples = (0..samples)
+		.map(|i| {
+			let theta_unwrapped = (i as f64 * 0.37).sin() * 9.0;
+			SamplePoint {
+				period: i + 1,
+				t: (i + 1) as f64 * 9.42477796076938,
+				theta: crate::dynamics::wrap_angle_pi(theta_unwrapped),
+				theta_unwrapped,
+				omega: (i as f64 * 0.11).cos() * 2.0 - 0.5,
+			}
+		})
+		.collect();
+	let stats = IntegratorStats { steps_accepted: 1234, steps_rejected: 5, dt_smallest: 1e-3, dt_largest: 0.25, wall_seconds: 1.5 };
+	RunOutcome { samples, cancelled: false, stats }
+}
@@ -9,6 +9,7 @@ pub enum IntegratorMethod {
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct PhysicalParams {
     pub g: f64,
     pub l: f64,
@@ -18,6 +19,7 @@ pub struct PhysicalParams {
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct IntegratorParams {
     pub method: IntegratorMethod,
     pub dt_user: Option<f64>,
@@ -31,6 +33,7 @@ pub struct IntegratorParams {
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct InitialState {
     pub theta0: f64,
     pub omega0: f64,
@@ -38,26 +41,252 @@ pub struct InitialState {
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct PoincareConfig {
     pub wrap_to_pi: bool
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataFormat {
+    Csv,
+    Npy,
+    Npz,
+    Ppb
+}
+
+impl DataFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            DataFormat::Csv => "csv",
+            DataFormat::Npy => "npy",
+            DataFormat::Npz => "npz",
+            DataFormat::Ppb => "ppb"
+        }
+    }
+}
+
+fn default_data_formats() -> Vec<DataFormat> {
+    vec![DataFormat::Csv]
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct OutputConfig {
-    pub out_base: String
+    pub out_base: String,
+    #[serde(default = "default_data_formats")]
+    pub data: Vec<DataFormat>,
+    #[serde(default)]
+    pub compress: bool
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Region {
+    pub name: String,
+    pub theta: [f64; 2],
+    pub omega: Option<[f64; 2]>,
+    pub title: Option<String>,
+    pub side_px: Option<u32>
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TilePyramid {
+    #[serde(default = "default_tile_levels")]
+    pub levels: u32,
+    #[serde(default = "default_tile_px")]
+    pub tile_px: u32
+}
+
+fn default_tile_levels() -> u32 {
+    6
+}
+
+fn default_tile_px() -> u32 {
+    256
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TickLabels {
+    #[default]
+    Numeric,
+    Pi
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderMode {
+    #[default]
+    Points,
+    Density
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SvgPoints {
+    #[default]
+    Circles,
+    Path,
+    Raster
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Colormap {
+    #[default]
+    Viridis,
+    Magma,
+    Inferno,
+    Plasma,
+    Greys
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DensityScale {
+    #[default]
+    Log,
+    Linear
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColourBy {
+    #[default]
+    None,
+    Period,
+    Ftle,
+    Energy,
+    Rotation
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkerShape {
+    #[default]
+    Circle,
+    Square,
+    Diamond,
+    Triangle
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SeriesStyle {
+    pub label: Option<String>,
+    pub colour: Option<String>,
+    #[serde(default)]
+    pub marker: MarkerShape
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LegendPosition {
+    #[default]
+    UpperRight,
+    UpperLeft,
+    LowerLeft,
+    LowerRight,
+    None
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    #[default]
+    Light,
+    Dark
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Aspect {
+    #[default]
+    Free,
+    Equal
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct PlotView {
     pub side_px: u32,
     pub title: String,
+    pub width_px: Option<u32>,
+    pub height_px: Option<u32>,
+    #[serde(default)]
+    pub aspect: Aspect,
+    pub aspect_ratio: Option<f64>,
+    pub dpi: Option<f64>,
     pub marker_size: Option<u32>,
     pub title_font_px: Option<u32>,
     pub axis_label_font_px: Option<u32>,
-    pub tick_font_px: Option<u32>
+    pub tick_font_px: Option<u32>,
+    pub x_range: Option<[f64; 2]>,
+    pub y_range: Option<[f64; 2]>,
+    pub x_tick_step: Option<f64>,
+    pub y_tick_step: Option<f64>,
+    pub strip_rows: Option<u32>,
+    pub html_point_budget: Option<u64>,
+    #[serde(default)]
+    pub x_tick_labels: TickLabels,
+    #[serde(default)]
+    pub y_tick_labels: TickLabels,
+    #[serde(default)]
+    pub render: RenderMode,
+    #[serde(default)]
+    pub antialias: bool,
+    #[serde(default)]
+    pub svg_points: SvgPoints,
+    #[serde(default)]
+    pub colormap: Colormap,
+    #[serde(default)]
+    pub density_scale: DensityScale,
+    #[serde(default)]
+    pub colour_by: ColourBy,
+    #[serde(default)]
+    pub series: Vec<SeriesStyle>,
+    #[serde(default)]
+    pub legend: LegendPosition,
+    #[serde(default)]
+    pub theme: Theme,
+    pub background: Option<String>,
+    pub foreground: Option<String>,
+    pub marker_colour: Option<String>,
+    pub marker_opacity: Option<f64>,
+    pub font_family: Option<String>,
+    pub x_label: Option<String>,
+    pub y_label: Option<String>,
+    pub frame_width: Option<u32>,
+    #[serde(default)]
+    pub regions: Vec<Region>,
+    pub tiles: Option<TilePyramid>
+}
+
+impl PlotView {
+    pub fn canvas_width(&self) -> u32 {
+        self.width_px.unwrap_or(self.side_px)
+    }
+
+    // None when the height depends on the plotted ranges (`aspect: equal`).
+    pub fn canvas_height(&self) -> Option<u32> {
+        match (self.aspect, self.aspect_ratio) {
+            (Aspect::Equal, _) => None,
+            (Aspect::Free, Some(ratio)) => Some((self.canvas_width() as f64 / ratio).round().max(1.0) as u32),
+            (Aspect::Free, None) => Some(self.height_px.unwrap_or(self.side_px)),
+        }
+    }
+
+    // Factor from the CSS pixels (1/96 inch) that font, marker and margin sizes are given in to
+    // canvas pixels.
+    pub fn dpi_scale(&self) -> f64 {
+        self.dpi.map_or(1.0, |dpi| dpi / 96.0)
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct RunSpec {
     pub phys: PhysicalParams,
     pub integrator: IntegratorParams,
@@ -74,8 +303,37 @@ pub struct State {
     pub omega: f64
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct IntegratorStats {
+    pub steps_accepted: u64,
+    pub steps_rejected: u64,
+    pub dt_smallest: f64,
+    pub dt_largest: f64,
+    pub wall_seconds: f64
+}
+
+impl IntegratorStats {
+    pub fn accept(&mut self, dt: f64) {
+        if self.steps_accepted == 0 {
+            self.dt_smallest = dt;
+            self.dt_largest = dt;
+        } else {
+            self.dt_smallest = self.dt_smallest.min(dt);
+            self.dt_largest = self.dt_largest.max(dt);
+        }
+        self.steps_accepted += 1;
+    }
+
+    pub fn reject(&mut self) {
+        self.steps_rejected += 1;
+    }
+}
+
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct SamplePoint {
+    pub period: usize,
+    pub t: f64,
     pub theta: f64,
+    pub theta_unwrapped: f64,
     pub omega: f64
 }
\ No newline at end of file